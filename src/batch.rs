@@ -0,0 +1,64 @@
+use crate::{
+	errors::{EdbError, EdbErrorKind, EdbResult},
+	WriteOutcome,
+};
+
+/// The outcome of an operation that writes many keys, such as
+/// [`import_json`][crate::EasyDB::import_json].
+///
+/// A failed key doesn't stop the rest of the batch, so check `failed` before assuming everything
+/// was written.
+#[derive(Debug, Default)]
+pub struct BatchReport {
+	/// Keys that were written successfully.
+	pub succeeded: Vec<String>,
+	/// Keys that were deliberately left alone.
+	pub skipped: Vec<String>,
+	/// Keys that couldn't be written, with the reason.
+	pub failed: Vec<(String, EdbError)>,
+}
+
+impl BatchReport {
+	/// Returns `true` if no key failed.
+	pub fn is_success(&self) -> bool {
+		self.failed.is_empty()
+	}
+	/// Turns a report with failures into a single [`Batch`][EdbErrorKind::Batch] error, so a
+	/// batch can be checked with `?`. A report without failures is returned as-is.
+	///
+	/// ```
+	/// # use easydb::{errors::EdbErrorKind, BatchReport};
+	/// let mut report = BatchReport::default();
+	/// report.succeeded.push("a".to_string());
+	/// assert!(report.into_result().is_ok());
+	/// ```
+	pub fn into_result(self) -> EdbResult<Self> {
+		if self.is_success() {
+			Ok(self)
+		} else {
+			Err(EdbErrorKind::Batch(self.failed).into())
+		}
+	}
+	/// Records the result of writing `key`, treating a non-2xx status as a failure.
+	pub(crate) fn record(&mut self, key: String, result: EdbResult<WriteOutcome>) {
+		match result {
+			Ok(outcome) if outcome.is_success() => self.succeeded.push(key),
+			Ok(outcome) => self.failed.push((key, outcome.into_error())),
+			Err(e) => self.failed.push((key, e)),
+		}
+	}
+}
+
+/// What [`import_json`][crate::EasyDB::import_json] does with a key that already exists in the
+/// database.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OnConflict {
+	/// Replace the existing value. This is the default.
+	#[default]
+	Overwrite,
+	/// Keep the existing value and report the key as skipped.
+	Skip,
+	/// Fail with [`ImportConflict`][crate::errors::EdbErrorKind::ImportConflict] before writing
+	/// anything.
+	Error,
+}
@@ -0,0 +1,530 @@
+use crate::{
+	errors::{EdbError, EdbResult},
+	mock::Shutdown,
+	rate_limit::RateLimiter,
+	retry::{RetryContext, RetryPredicate},
+	semaphore::Semaphore,
+	EasyDB, JsonCodec,
+};
+use rand::Rng;
+use reqwest::{header::HeaderName, Client, RedirectPolicy};
+use std::{convert::TryFrom, sync::Arc, time::Duration};
+
+/// A builder for an [`EasyDB`] with a customized HTTP client.
+///
+/// Create one with [`EasyDB::builder`], or start from an existing handle with
+/// [`EasyDB::to_builder`]. Options for the HTTP client default to `reqwest`'s own defaults.
+///
+/// # Example
+///
+/// ```
+/// # use easydb::{EasyDB, errors::EdbError};
+/// let edb = EasyDB::builder()
+///     .uuid("aaaa...".to_string())
+///     .token("bbbb...".to_string())
+///     .http2_prior_knowledge(true)
+///     .build()?;
+/// # Ok::<(), EdbError>(())
+/// ```
+///
+#[derive(Clone, Debug, Default)]
+pub struct EasyDBBuilder {
+	uuid: Option<String>,
+	token: Option<String>,
+	url: Option<String>,
+	/// A client to reuse, cleared whenever a setting that the client is built from changes.
+	client: Option<Client>,
+	options: Options,
+}
+
+/// Settings that the HTTP client is built from.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ClientOptions {
+	pub http2_prior_knowledge: bool,
+	pub disable_gzip: bool,
+	pub max_idle_per_host: Option<usize>,
+	pub timeout: Option<Duration>,
+	pub max_redirects: Option<usize>,
+	/// The client was passed in by the caller, so it can't be rebuilt from these settings.
+	pub custom: bool,
+}
+
+impl ClientOptions {
+	pub fn with_timeout(&self, timeout: Duration) -> Self {
+		Self {
+			timeout: Some(timeout),
+			..self.clone()
+		}
+	}
+	pub fn build(&self) -> EdbResult<Client> {
+		let mut client = Client::builder();
+		if self.http2_prior_knowledge {
+			client = client.h2_prior_knowledge();
+		}
+		if self.disable_gzip {
+			client = client.gzip(false);
+		}
+		if let Some(max) = self.max_idle_per_host {
+			client = client.max_idle_per_host(max);
+		}
+		if let Some(timeout) = self.timeout {
+			client = client.timeout(timeout);
+		}
+		match self.max_redirects {
+			Some(0) => client = client.redirect(RedirectPolicy::none()),
+			Some(max) => client = client.redirect(RedirectPolicy::limited(max)),
+			None => {}
+		}
+		Ok(client.build()?)
+	}
+}
+
+/// Overrides for a single call, for methods such as [`get_with_opts`][EasyDB::get_with_opts].
+///
+/// Anything left as `None` uses the setting the `EasyDB` was built with.
+///
+/// Overriding the timeout needs a second HTTP client, because `reqwest` 0.9 only supports
+/// timeouts per client. It's built from the same settings on first use and kept for later calls
+/// with the same timeout. A handle whose client was passed to
+/// [`from_parts`][EasyDB::from_parts] can't be rebuilt this way, so its calls fail instead.
+#[derive(Clone, Debug, Default)]
+pub struct RequestOptions {
+	/// Replaces the [`timeout`][EasyDBBuilder::timeout].
+	pub timeout: Option<Duration>,
+	/// Replaces [`max_retries`][EasyDBBuilder::max_retries].
+	pub max_retries: Option<u32>,
+	/// Query parameters to append to the request URL, percent-encoded as needed.
+	///
+	/// easydb.io doesn't define any parameters yet, so this is for API additions and for
+	/// proxies that read them.
+	pub query: Vec<(String, String)>,
+	/// Sent as the request ID instead of a generated one. Ignored unless
+	/// [`request_id_header`][EasyDBBuilder::request_id_header] is set.
+	pub request_id: Option<String>,
+}
+
+/// What [`EasyDBBuilder::trailing_slash`] does with a URL that doesn't end with a slash.
+///
+/// Relative URLs are joined like links in a browser, so without the slash the last segment is
+/// replaced: the UUID `abcd` joined onto `https://app.easydb.io/database` would give
+/// `https://app.easydb.io/abcd`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TrailingSlash {
+	/// Add the missing slash, logging a warning when the `logging` feature is enabled. This is
+	/// the default.
+	#[default]
+	Lenient,
+	/// Fail with [`MissingTrailingSlash`][crate::errors::EdbErrorKind::MissingTrailingSlash].
+	Strict,
+}
+
+/// What [`EasyDBBuilder::missing_key_behavior`] makes reads return for a missing key.
+///
+/// easydb.io answers a missing key with an empty string, so a key set to `""` counts as
+/// missing too. The `try_*` methods, such as [`try_get_json`][EasyDB::try_get_json], return
+/// `None` for a missing key whatever this is set to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MissingKeyBehavior {
+	/// Return an empty string. This is the default.
+	#[default]
+	EmptyString,
+	/// Make [`get_json`][EasyDB::get_json] return `Json::Null`. [`get`][EasyDB::get] can only
+	/// return strings, so it still returns an empty string.
+	Null,
+	/// Fail with [`MissingKey`][crate::errors::EdbErrorKind::MissingKey].
+	Error,
+}
+
+/// Settings that affect how an [`EasyDB`] behaves.
+#[derive(Clone, Debug)]
+pub(crate) struct Options {
+	pub client: ClientOptions,
+	pub pretty_bodies: bool,
+	pub detect_stale_reads: bool,
+	pub max_key_url_len: usize,
+	pub max_retries: u32,
+	pub retry_backoff: Duration,
+	pub retry_jitter: bool,
+	pub content_type: String,
+	pub trim_keys: bool,
+	pub read_only: bool,
+	pub codec: Option<Arc<dyn JsonCodec>>,
+	pub fallback_tokens: Vec<String>,
+	pub trailing_slash: TrailingSlash,
+	pub checksums: bool,
+	pub dry_run: bool,
+	pub rate_limit: Option<Arc<RateLimiter>>,
+	pub retry_on: Option<RetryPredicate>,
+	pub max_concurrency: Option<Arc<Semaphore>>,
+	pub missing_key: MissingKeyBehavior,
+	pub request_id_header: Option<String>,
+	/// Keeps the server behind [`in_memory`][EasyDB::in_memory] running while a handle to it
+	/// exists.
+	pub mock: Option<Arc<Shutdown>>,
+}
+
+impl Default for Options {
+	fn default() -> Self {
+		Self {
+			client: ClientOptions::default(),
+			pretty_bodies: false,
+			detect_stale_reads: false,
+			max_key_url_len: 2000,
+			max_retries: 0,
+			retry_backoff: Duration::from_millis(100),
+			retry_jitter: true,
+			content_type: "application/json".to_string(),
+			trim_keys: false,
+			read_only: false,
+			codec: None,
+			fallback_tokens: Vec::new(),
+			trailing_slash: TrailingSlash::default(),
+			checksums: false,
+			dry_run: false,
+			rate_limit: None,
+			retry_on: None,
+			max_concurrency: None,
+			missing_key: MissingKeyBehavior::default(),
+			request_id_header: None,
+			mock: None,
+		}
+	}
+}
+
+impl Options {
+	/// How long to wait before retrying after `attempt` (counting from 0) failed.
+	pub fn retry_delay(&self, attempt: u32) -> Duration {
+		let max = self
+			.retry_backoff
+			.checked_mul(2u32.saturating_pow(attempt))
+			.unwrap_or(Duration::MAX);
+		if !self.retry_jitter {
+			return max;
+		}
+		let nanos = u64::try_from(max.as_nanos()).unwrap_or(u64::MAX);
+		Duration::from_nanos(rand::thread_rng().gen_range(0, nanos.saturating_add(1)))
+	}
+}
+
+impl EasyDBBuilder {
+	/// Creates a builder with nothing set.
+	pub fn new() -> Self {
+		Self::default()
+	}
+	pub(crate) fn prefilled(
+		uuid: String,
+		token: String,
+		url: String,
+		client: Client,
+		options: Options,
+	) -> Self {
+		Self {
+			uuid: Some(uuid),
+			token: Some(token),
+			url: Some(url),
+			client: Some(client),
+			options,
+		}
+	}
+	/// Sets the UUID. Required.
+	pub fn uuid(mut self, uuid: String) -> Self {
+		self.uuid = Some(uuid);
+		self
+	}
+	/// Sets the token. Required.
+	pub fn token(mut self, token: String) -> Self {
+		self.token = Some(token);
+		self
+	}
+	/// Sets tokens to try, in order, when a request with the main token returns 401
+	/// Unauthorized. Defaults to none.
+	///
+	/// This allows rotating tokens without downtime: configure the new token as the main one and
+	/// keep the old one as a fallback until every copy of the database has been switched over.
+	/// Every fallback is tried on every unauthorized request, so while the main token is rejected,
+	/// each call sends one extra request per fallback tried. The last response is returned
+	/// if every token is rejected.
+	pub fn fallback_tokens(mut self, tokens: Vec<String>) -> Self {
+		self.options.fallback_tokens = tokens;
+		self
+	}
+	/// Sets the URL. Defaults to `https://app.easydb.io/database/`.
+	pub fn url(mut self, url: String) -> Self {
+		self.url = Some(url);
+		self
+	}
+	/// Sets what to do if the URL doesn't end with a slash. Defaults to
+	/// [`TrailingSlash::Lenient`], which adds one.
+	pub fn trailing_slash(mut self, mode: TrailingSlash) -> Self {
+		self.options.trailing_slash = mode;
+		self
+	}
+	/// Talks HTTP/2 to the server without negotiating it first.
+	///
+	/// This lets many small requests share a single multiplexed connection, but every request
+	/// will fail if the server (or a proxy in between) doesn't speak HTTP/2.
+	pub fn http2_prior_knowledge(mut self, enable: bool) -> Self {
+		self.options.client.http2_prior_knowledge = enable;
+		self.client = None;
+		self
+	}
+	/// Asks for gzip-compressed responses and decompresses them. Defaults to `true`.
+	///
+	/// Compression is transparent: [`list_writer`][EasyDB::list_writer] and
+	/// [`get_writer`][EasyDB::get_writer] write the decompressed body. `reqwest` 0.9 only
+	/// supports gzip, so bodies in other encodings, such as brotli, fail to parse. Disable this
+	/// to receive the body exactly as the server sent it.
+	pub fn gzip(mut self, enable: bool) -> Self {
+		self.options.client.disable_gzip = !enable;
+		self.client = None;
+		self
+	}
+	/// Sets how many idle connections to keep alive for reuse. Defaults to no limit.
+	///
+	/// `reqwest` 0.9 doesn't expose TCP-level keepalive, so this is the only keep-alive setting
+	/// available.
+	pub fn max_idle_per_host(mut self, max: usize) -> Self {
+		self.options.client.max_idle_per_host = Some(max);
+		self.client = None;
+		self
+	}
+	/// Sets how long a request may take, from connecting until the whole response has been
+	/// read, before it fails. Defaults to 30 seconds.
+	pub fn timeout(mut self, timeout: Duration) -> Self {
+		self.options.client.timeout = Some(timeout);
+		self.client = None;
+		self
+	}
+	/// Sets how many redirects to follow before giving up. Defaults to 10.
+	///
+	/// With 0, redirects aren't followed at all: the redirect response itself is returned, so
+	/// an unexpected redirect shows up as a 3xx status instead of silently reaching another host.
+	pub fn max_redirects(mut self, max: usize) -> Self {
+		self.options.client.max_redirects = Some(max);
+		self.client = None;
+		self
+	}
+	/// Sends pretty-printed JSON request bodies instead of compact ones. Defaults to `false`.
+	///
+	/// Only useful for reading requests through a logging proxy while debugging. Ignored when a
+	/// [`json_codec`][EasyDBBuilder::json_codec] is set.
+	pub fn pretty_bodies(mut self, enable: bool) -> Self {
+		self.options.pretty_bodies = enable;
+		self
+	}
+	/// Remembers the last value written to each key, and makes [`get`][EasyDB::get] and
+	/// [`get_json`][EasyDB::get_json] return a
+	/// [`StaleRead`][crate::errors::EdbErrorKind::StaleRead] error when the database returns
+	/// something else. Defaults to `false`.
+	///
+	/// A key is forgotten once a read returns the written value, but until then a copy of the
+	/// value is kept in memory, so writing many large values without reading them back can use a
+	/// lot of memory.
+	pub fn detect_stale_reads(mut self, enable: bool) -> Self {
+		self.options.detect_stale_reads = enable;
+		self
+	}
+	/// Sets the `Content-Type` header sent with request bodies. Defaults to `application/json`.
+	///
+	/// Bodies are always JSON, so this is only for proxies or deployments that expect a charset
+	/// or a vendor type, such as `application/json; charset=utf-8`.
+	pub fn content_type(mut self, content_type: String) -> Self {
+		self.options.content_type = content_type;
+		self
+	}
+	/// Trims leading and trailing whitespace from keys before they're used. Defaults to `false`.
+	///
+	/// URL parsing already drops ASCII spaces and newlines at either end of a key, but other
+	/// whitespace, such as a non-breaking space pasted along with the key, is sent as part of the
+	/// URL and reaches a different key. This trims all Unicode whitespace instead. Values are
+	/// always stored exactly as given.
+	pub fn trim_keys(mut self, enable: bool) -> Self {
+		self.options.trim_keys = enable;
+		self
+	}
+	/// Makes every method that would change the database fail with
+	/// [`ReadOnly`][crate::errors::EdbErrorKind::ReadOnly] without sending a request. Defaults to
+	/// `false`.
+	///
+	/// This covers `put`, `delete`, `clear`, the batch helpers, and any
+	/// [`raw_request`][EasyDB::raw_request] other than `GET` or `HEAD`. It's enforced by this
+	/// handle only, so the token itself can still write.
+	pub fn read_only(mut self, enable: bool) -> Self {
+		self.options.read_only = enable;
+		self
+	}
+	/// Stores a checksum with every value and verifies it on every read. Defaults to `false`.
+	///
+	/// Each value is stored as `{"v": <value>, "crc": <checksum>}`, where the checksum is the
+	/// CRC-32 (IEEE) of the value's compact JSON text with object keys in sorted order.
+	/// [`get_json`][EasyDB::get_json], [`list_json`][EasyDB::list_json],
+	/// [`list_ordered`][EasyDB::list_ordered], and the methods built on them unwrap the value, so
+	/// an [`export_json`][EasyDB::export_json] backup holds the plain values. They fail with
+	/// [`ChecksumMismatch`][crate::errors::EdbErrorKind::ChecksumMismatch] if the checksum
+	/// doesn't match or the value isn't in this format. Missing keys still read as an empty
+	/// string.
+	///
+	/// Only clients with this enabled can read the values it writes, and it can't read values
+	/// written without it. Raw access such as [`get_writer`][EasyDB::get_writer] and
+	/// [`compact`][EasyDB::compact] sees the stored format unchanged.
+	pub fn checksums(mut self, enable: bool) -> Self {
+		self.options.checksums = enable;
+		self
+	}
+	/// Skips every request that would change the database, and answers it with a simulated
+	/// `200 OK` instead. Defaults to `false`.
+	///
+	/// This covers the same requests as [`read_only`][EasyDBBuilder::read_only], but instead of
+	/// failing they report success, so a migration or cleanup script can run to completion
+	/// without changing anything. Reads still go to the server, so later steps see the
+	/// unchanged data. With the `logging` feature enabled, each skipped request is logged.
+	pub fn dry_run(mut self, enable: bool) -> Self {
+		self.options.dry_run = enable;
+		self
+	}
+	/// Sends a random UUID in the header `name`, such as `X-Request-Id`, with every request, so
+	/// requests can be matched with server and proxy logs. Defaults to sending no request ID.
+	///
+	/// Each request gets its own ID, which is kept for its retries and
+	/// [`fallback_tokens`][EasyDBBuilder::fallback_tokens]. Set
+	/// [`RequestOptions::request_id`] to choose the ID for one call. With the `logging` feature
+	/// enabled, every message about a request starts with its ID in brackets. The token is never
+	/// logged.
+	///
+	/// # Errors
+	///
+	/// [`build`][EasyDBBuilder::build] will fail if `name` isn't a valid header name.
+	pub fn request_id_header(mut self, name: String) -> Self {
+		self.options.request_id_header = Some(name);
+		self
+	}
+	/// Sets what [`get`][EasyDB::get], [`get_json`][EasyDB::get_json], and the methods built on
+	/// them return for a missing key. Defaults to [`MissingKeyBehavior::EmptyString`].
+	pub fn missing_key_behavior(mut self, behavior: MissingKeyBehavior) -> Self {
+		self.options.missing_key = behavior;
+		self
+	}
+	/// Uses `codec` instead of `serde_json` to encode request bodies and decode responses. See
+	/// [`JsonCodec`] for which methods it applies to.
+	pub fn json_codec(mut self, codec: impl JsonCodec + 'static) -> Self {
+		self.options.codec = Some(Arc::new(codec));
+		self
+	}
+	/// Sets the longest URL, in bytes, that a key may produce. Defaults to 2000.
+	///
+	/// Servers and proxies often reject long URLs with unhelpful errors, so keys that would go
+	/// over this limit fail with [`KeyTooLong`][crate::errors::EdbErrorKind::KeyTooLong] before
+	/// any request is sent.
+	pub fn max_key_url_len(mut self, len: usize) -> Self {
+		self.options.max_key_url_len = len;
+		self
+	}
+	/// Sets how many times to retry a request after a network error, a 5xx status, or a 429
+	/// status. Defaults to 0.
+	///
+	/// With the `logging` feature enabled, each retry and the final outcome are logged.
+	pub fn max_retries(mut self, retries: u32) -> Self {
+		self.options.max_retries = retries;
+		self
+	}
+	/// Limits this handle to sending `requests_per_sec` requests each second, waiting before a
+	/// request until it's allowed. Defaults to no limit, and a value that isn't positive removes
+	/// the limit.
+	///
+	/// Requests are spaced evenly, with no bursts, and every attempt counts, retries included.
+	/// Requests skipped by [`dry_run`][EasyDBBuilder::dry_run] don't. The limit is shared by
+	/// every handle made from this one with [`to_builder`][EasyDB::to_builder] or
+	/// [`with_token`][EasyDB::with_token], and by the threads of
+	/// [`put_many`][EasyDB::put_many], so a batch job stays under it as a whole.
+	pub fn rate_limit(mut self, requests_per_sec: f64) -> Self {
+		self.options.rate_limit = RateLimiter::new(requests_per_sec).map(Arc::new);
+		self
+	}
+	/// Limits this handle to `max` requests in flight at once, making any more wait for one to
+	/// finish. Defaults to no limit, and 0 removes the limit.
+	///
+	/// A request holds its slot from when it's sent until its whole response body has been read,
+	/// but not while waiting between retries. So with a limit, every response is read into
+	/// memory before it's returned, including those of streaming methods such as
+	/// [`get_writer`][EasyDB::get_writer]. The limit is shared the same way as
+	/// [`rate_limit`][EasyDBBuilder::rate_limit], so it bounds every thread using this handle or
+	/// one made from it, however many there are.
+	pub fn max_concurrency(mut self, max: usize) -> Self {
+		self.options.max_concurrency = Semaphore::new(max).map(Arc::new);
+		self
+	}
+	/// Decides which failed attempts are retried with `predicate` instead of the built-in policy,
+	/// which retries network errors, 5xx statuses, and 429 statuses.
+	///
+	/// `predicate` is called after every attempt, including successful ones, and the attempt is
+	/// retried if it returns `true`. [`max_retries`][EasyDBBuilder::max_retries] still limits how
+	/// many times, so set it as well. Returning `true` for a response makes that response count
+	/// as a failure: once the retries run out, it's returned as the final response anyway.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use easydb::{EasyDB, errors::EdbError};
+	/// // Also retry the 404 a proxy returns while the database restarts.
+	/// let edb = EasyDB::builder()
+	///     .uuid("aaaa...".to_string())
+	///     .token("bbbb...".to_string())
+	///     .max_retries(3)
+	///     .retry_on(|ctx| ctx.status() == Some(404) || ctx.is_retryable_by_default())
+	///     .build()?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	pub fn retry_on<F>(mut self, predicate: F) -> Self
+	where
+		F: Fn(&RetryContext) -> bool + Send + Sync + 'static,
+	{
+		self.options.retry_on = Some(RetryPredicate(Arc::new(predicate)));
+		self
+	}
+	/// Sets the delay before the first retry. Each later retry waits twice as long as the one
+	/// before it. Defaults to 100 milliseconds.
+	///
+	/// With [`retry_jitter`][EasyDBBuilder::retry_jitter] enabled, this is the longest possible
+	/// delay rather than the exact one.
+	pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+		self.options.retry_backoff = backoff;
+		self
+	}
+	/// Waits a random fraction of each retry delay instead of the full delay. Defaults to `true`.
+	///
+	/// Without jitter, clients that failed at the same moment retry at the same moments too, and
+	/// can keep overloading a recovering server. With jitter, a retry after `attempt` failures
+	/// waits anywhere from zero up to `retry_backoff * 2^attempt`. Disable it when the exact
+	/// schedule matters, such as in tests.
+	pub fn retry_jitter(mut self, enable: bool) -> Self {
+		self.options.retry_jitter = enable;
+		self
+	}
+	/// Creates the `EasyDB`.
+	///
+	/// # Errors
+	///
+	/// Will fail if the UUID or token wasn't set, if `url` or `uuid` don't form a valid URL, or if
+	/// the HTTP client can't be created.
+	pub fn build(self) -> EdbResult<EasyDB> {
+		let uuid = self
+			.uuid
+			.ok_or_else(|| EdbError::message("UUID is required"))?;
+		let token = self
+			.token
+			.ok_or_else(|| EdbError::message("Token is required"))?;
+		if let Some(name) = &self.options.request_id_header {
+			HeaderName::from_bytes(name.as_bytes())
+				.map_err(|_| EdbError::message(format!("Invalid request ID header: {}", name)))?;
+		}
+		let mut options = self.options;
+		let client = match self.client {
+			Some(client) => client,
+			None => {
+				options.client.custom = false;
+				options.client.build()?
+			}
+		};
+		EasyDB::with_client(uuid, token, self.url, client, options)
+	}
+}
@@ -0,0 +1,69 @@
+//! The value format used by [`checksums`][crate::EasyDBBuilder::checksums].
+
+use crate::{
+	errors::{EdbErrorKind, EdbResult},
+	Json,
+};
+use serde_json::json;
+
+/// Returns the CRC-32 of `value`'s compact JSON text, with object keys in sorted order.
+fn checksum(value: &Json) -> u32 {
+	let mut text = String::new();
+	write_canonical(value, &mut text);
+	crc32fast::hash(text.as_bytes())
+}
+
+/// Writes `value` as compact JSON with the keys of every object sorted.
+///
+/// `serde_json` only sorts keys while its `preserve_order` feature is off, and any crate in the
+/// build can turn it on, so equal values could otherwise give different text.
+fn write_canonical(value: &Json, out: &mut String) {
+	match value {
+		Json::Array(items) => {
+			out.push('[');
+			for (i, item) in items.iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				write_canonical(item, out);
+			}
+			out.push(']');
+		}
+		Json::Object(object) => {
+			let mut entries: Vec<_> = object.iter().collect();
+			entries.sort_by(|a, b| a.0.cmp(b.0));
+			out.push('{');
+			for (i, (key, item)) in entries.into_iter().enumerate() {
+				if i > 0 {
+					out.push(',');
+				}
+				out.push_str(&Json::String(key.clone()).to_string());
+				out.push(':');
+				write_canonical(item, out);
+			}
+			out.push('}');
+		}
+		scalar => out.push_str(&scalar.to_string()),
+	}
+}
+
+/// Wraps `value` with its checksum.
+pub(crate) fn wrap(value: Json) -> Json {
+	let crc = checksum(&value);
+	json!({ "v": value, "crc": crc })
+}
+
+/// Verifies and unwraps a value stored by [`wrap`].
+pub(crate) fn unwrap(key: &str, stored: Json) -> EdbResult<Json> {
+	let mismatch = || EdbErrorKind::ChecksumMismatch(key.to_string()).into();
+	let mut object = match stored {
+		Json::Object(object) if object.len() == 2 => object,
+		_ => return Err(mismatch()),
+	};
+	let crc = object.remove("crc").and_then(|crc| crc.as_u64());
+	let value = object.remove("v").ok_or_else(mismatch)?;
+	if crc != Some(u64::from(checksum(&value))) {
+		return Err(mismatch());
+	}
+	Ok(value)
+}
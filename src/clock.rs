@@ -0,0 +1,36 @@
+use std::{fmt, time::Duration};
+
+/// How far the server's clock is from the local one, as returned by
+/// [`server_time_offset`][crate::EasyDB::server_time_offset].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClockOffset {
+	/// The server's clock is ahead of the local one by this much, or the clocks agree.
+	Ahead(Duration),
+	/// The server's clock is behind the local one by this much.
+	Behind(Duration),
+}
+
+impl ClockOffset {
+	/// Returns the offset in seconds: positive if the server is ahead, negative if it's behind.
+	pub fn as_secs_f64(self) -> f64 {
+		match self {
+			ClockOffset::Ahead(d) => d.as_secs_f64(),
+			ClockOffset::Behind(d) => -d.as_secs_f64(),
+		}
+	}
+	/// Returns the size of the offset, whichever way it goes.
+	pub fn abs(self) -> Duration {
+		match self {
+			ClockOffset::Ahead(d) | ClockOffset::Behind(d) => d,
+		}
+	}
+}
+
+impl fmt::Display for ClockOffset {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			ClockOffset::Ahead(d) => write!(f, "Server is {:?} ahead", d),
+			ClockOffset::Behind(d) => write!(f, "Server is {:?} behind", d),
+		}
+	}
+}
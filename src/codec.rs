@@ -0,0 +1,48 @@
+use crate::{errors::EdbResult, Json};
+use std::fmt::Debug;
+
+/// Converts between [`Json`] values and the bytes sent to and received from the server.
+///
+/// By default, `serde_json` is used directly. Install a different codec with
+/// [`json_codec`][crate::EasyDBBuilder::json_codec] to use another parser for large
+/// [`list_json`][crate::EasyDB::list_json] responses, or to control how request bodies are
+/// formatted.
+///
+/// The codec handles request bodies and the responses of [`get_json`][crate::EasyDB::get_json]
+/// and [`list_json`][crate::EasyDB::list_json], along with the methods built on them. Methods
+/// that depend on `serde_json` parsing details, such as
+/// [`count_matching`][crate::EasyDB::count_matching], always use `serde_json`.
+/// [`list_ordered`][crate::EasyDB::list_ordered] decodes its values with the codec, but also
+/// parses the body with `serde_json` to find their order.
+///
+/// # Example
+///
+/// ```
+/// # use easydb::{EasyDB, JsonCodec, Json, errors::EdbResult};
+/// /// Sends request bodies with two-space indentation.
+/// #[derive(Debug)]
+/// struct Pretty;
+///
+/// impl JsonCodec for Pretty {
+///     fn encode(&self, value: &Json) -> EdbResult<String> {
+///         Ok(serde_json::to_string_pretty(value)?)
+///     }
+///     fn decode(&self, bytes: &[u8]) -> EdbResult<Json> {
+///         Ok(serde_json::from_slice(bytes)?)
+///     }
+/// }
+///
+/// let edb = EasyDB::builder()
+///     .uuid("aaaa...".to_string())
+///     .token("bbbb...".to_string())
+///     .json_codec(Pretty)
+///     .build()?;
+/// # Ok::<(), easydb::errors::EdbError>(())
+/// ```
+///
+pub trait JsonCodec: Debug + Send + Sync {
+	/// Serializes a request body.
+	fn encode(&self, value: &Json) -> EdbResult<String>;
+	/// Parses a response body.
+	fn decode(&self, bytes: &[u8]) -> EdbResult<Json>;
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// The credentials and URL of an [`EasyDB`][crate::EasyDB], without its HTTP client or
+/// settings.
+///
+/// Get one with [`config`][crate::EasyDB::config] or [`into_config`][crate::EasyDB::into_config],
+/// and turn it back into an `EasyDB` with `EasyDB::try_from`. It serializes with the same field
+/// names as `easydb.toml`, so it can be written back out as a config file.
+///
+/// The `Debug` output hides the token, so a config can be logged safely.
+///
+/// # Example
+///
+/// ```
+/// # use std::convert::TryFrom;
+/// # use easydb::{EasyDB, errors::EdbError};
+/// let edb = EasyDB::from_uuid_token("abcd".to_string(), "efgh".to_string(), None)?;
+/// let config = edb.into_config();
+/// assert_eq!(config.url, "https://app.easydb.io/database/");
+/// let edb = EasyDB::try_from(config)?;
+/// assert_eq!(edb.uuid(), "abcd");
+/// # Ok::<(), EdbError>(())
+/// ```
+///
+#[derive(Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EasyDBConfig {
+	/// The database's UUID.
+	#[serde(rename = "UUID")]
+	pub uuid: String,
+	/// The token used to authenticate.
+	#[serde(rename = "Token")]
+	pub token: String,
+	/// The base URL that the UUID is joined onto.
+	#[serde(rename = "URL")]
+	pub url: String,
+}
+
+impl fmt::Debug for EasyDBConfig {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("EasyDBConfig")
+			.field("uuid", &self.uuid)
+			.field("token", &"<redacted>")
+			.field("url", &self.url)
+			.finish()
+	}
+}
+
+/// Everything [`from_toml_str_full`][crate::EasyDB::from_toml_str_full] reads.
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub(crate) struct FullConfig {
+	#[serde(rename = "UUID")]
+	pub uuid: String,
+	pub token: String,
+	#[serde(rename = "URL")]
+	pub url: Option<String>,
+	/// In seconds.
+	pub timeout: Option<f64>,
+	pub max_retries: Option<u32>,
+	/// In requests per second.
+	pub rate_limit: Option<f64>,
+	pub read_only: Option<bool>,
+}
@@ -1,17 +1,54 @@
-use crate::errors::{EdbError, EdbResult, EdbResultExt};
+use crate::{
+	batch::{BatchReport, OnConflict},
+	builder::{ClientOptions, MissingKeyBehavior, Options, RequestOptions, TrailingSlash},
+	checksum,
+	clock::ClockOffset,
+	config::{EasyDBConfig, FullConfig},
+	errors::{EdbError, EdbErrorKind, EdbResult, EdbResultExt},
+	health::{Health, HealthStatus},
+	mock,
+	ordered::OrderedEntries,
+	outcome::WriteOutcome,
+	paged::PagedView,
+	retry::RetryContext,
+	stats::{self, DbStats},
+	sync::{self, SyncReport},
+	watch::{self, WatchHandle},
+	EasyDBBuilder,
+};
 use reqwest::{
-	header::{CONTENT_LENGTH, CONTENT_TYPE},
-	Client, Url,
+	header::{CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, DATE},
+	r#async::ResponseBuilderExt,
+	Client, Method, Response, StatusCode, Url,
+};
+use serde::{
+	de::{DeserializeOwned, IgnoredAny},
+	Deserialize, Serialize, Serializer,
 };
-use serde::{Deserialize, Serialize};
-use serde_json::json;
 pub use serde_json::Value as Json;
-use std::{collections::HashMap, fs::read_to_string, io::Write, str::FromStr};
+use serde_json::{json, Map};
+use std::{
+	collections::{hash_map, BTreeMap, HashMap, HashSet},
+	convert::TryFrom,
+	env,
+	fs::read_to_string,
+	io::{Read, Write},
+	path::{Path, PathBuf},
+	str::FromStr,
+	sync::{
+		atomic::{AtomicBool, AtomicUsize, Ordering},
+		mpsc::Receiver,
+		Arc, Mutex,
+	},
+	thread,
+	time::{Duration, Instant, SystemTime},
+};
 #[derive(Debug, Deserialize, Serialize)]
 
 /// The main type for dealing with easydb.
 ///
-/// Create an `EasyDB` using [`new`][EasyDB::new] or [`from_uuid_token`][EasyDB::from_uuid_token].
+/// Create an `EasyDB` using [`new`][EasyDB::new], [`from_uuid_token`][EasyDB::from_uuid_token], or
+/// [`builder`][EasyDB::builder].
 ///
 pub struct EasyDB {
 	#[serde(rename = "UUID")]
@@ -20,14 +57,176 @@ pub struct EasyDB {
 	token: String,
 	#[serde(skip, default = "Client::new")]
 	client: Client,
-	#[serde(rename = "URL", default = "default_url")]
-	url: String,
+	#[serde(rename = "URL", default = "default_url", with = "url_string")]
+	url: Url,
+	#[serde(skip)]
+	options: Options,
+	#[serde(skip)]
+	written: Arc<Mutex<HashMap<String, Json>>>,
+	/// Clients rebuilt for [`RequestOptions::timeout`], by timeout.
+	#[serde(skip)]
+	timeout_clients: Arc<Mutex<HashMap<Duration, Client>>>,
+}
+
+fn default_url() -> Url {
+	"https://app.easydb.io/database/".parse().unwrap()
+}
+
+/// Reads and writes the URL as a plain string in `easydb.toml`.
+mod url_string {
+	use reqwest::Url;
+	use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(url: &Url, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(url.as_str())
+	}
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Url, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(D::Error::custom)
+	}
+}
+
+/// The platform's config directories, most specific first.
+fn config_dirs() -> Vec<PathBuf> {
+	let var = |name| env::var_os(name).filter(|value| !value.is_empty());
+	let mut dirs = Vec::new();
+	if let Some(dir) = var("XDG_CONFIG_HOME") {
+		dirs.push(PathBuf::from(dir));
+	}
+	if cfg!(windows) {
+		if let Some(dir) = var("APPDATA") {
+			dirs.push(PathBuf::from(dir));
+		}
+	} else if let Some(home) = var("HOME") {
+		let home = PathBuf::from(home);
+		if cfg!(target_os = "macos") {
+			dirs.push(home.join("Library").join("Application Support"));
+		}
+		dirs.push(home.join(".config"));
+	}
+	dirs
+}
+
+/// Separates string values from the rest, returning the strings and the sorted keys of
+/// everything else.
+fn split_strings(map: HashMap<String, Json>) -> (HashMap<String, String>, Vec<String>) {
+	let mut strings = HashMap::new();
+	let mut others = Vec::new();
+	for (key, value) in map {
+		match value {
+			Json::String(s) => {
+				strings.insert(key, s);
+			}
+			_ => others.push(key),
+		}
+	}
+	others.sort();
+	(strings, others)
+}
+
+/// Fails with `Cancelled(done)` if `cancel` has been set.
+fn check_cancel(cancel: Option<&AtomicBool>, done: usize) -> EdbResult<()> {
+	match cancel {
+		Some(cancel) if cancel.load(Ordering::Relaxed) => Err(EdbErrorKind::Cancelled(done).into()),
+		_ => Ok(()),
+	}
+}
+
+/// Generates a random (version 4) UUID for [`request_id_header`][EasyDBBuilder::request_id_header].
+fn new_request_id() -> String {
+	let mut bytes: [u8; 16] = rand::random();
+	bytes[6] = (bytes[6] & 0x0f) | 0x40;
+	bytes[8] = (bytes[8] & 0x3f) | 0x80;
+	let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+	format!(
+		"{}-{}-{}-{}-{}",
+		&hex[..8],
+		&hex[8..12],
+		&hex[12..16],
+		&hex[16..20],
+		&hex[20..]
+	)
+}
+
+/// Formats a request ID to start a log message with, or nothing without one.
+fn log_prefix(request_id: Option<&String>) -> String {
+	match request_id {
+		Some(id) => format!("[{}] ", id),
+		None => String::new(),
+	}
+}
+
+/// Runs `f`, returning its result with how long it took.
+fn timed<T>(f: impl FnOnce() -> EdbResult<T>) -> EdbResult<(T, Duration)> {
+	let start = Instant::now();
+	let value = f()?;
+	Ok((value, start.elapsed()))
+}
+
+/// Reads the rest of `resp`'s body, so its transfer is finished, and returns an otherwise
+/// identical response that holds the body in memory.
+fn buffered(mut resp: Response) -> reqwest::Result<Response> {
+	let mut body = Vec::new();
+	resp.copy_to(&mut body)?;
+	let mut builder = http::Response::builder();
+	builder
+		.status(resp.status())
+		.version(resp.version())
+		.url(resp.url().clone());
+	let mut buffered = builder
+		.body(body)
+		.expect("parts of a received response are valid");
+	*buffered.headers_mut() = resp.headers().clone();
+	Ok(buffered.into())
 }
 
-fn default_url() -> String {
-	"https://app.easydb.io/database/".to_string()
+/// A response body that should contain JSON.
+struct Body {
+	status: StatusCode,
+	/// Whether the `Content-Type` says the body is HTML.
+	html: bool,
+	bytes: Vec<u8>,
 }
 
+impl Body {
+	/// Parses the body, failing with
+	/// [`NonJsonResponse`][crate::errors::EdbErrorKind::NonJsonResponse] instead of a JSON error
+	/// when the body is an HTML page or an unparseable error.
+	fn parse<T: DeserializeOwned>(&self) -> EdbResult<T> {
+		self.decode(|bytes| Ok(serde_json::from_slice(bytes)?))
+	}
+	/// Like [`parse`][Body::parse], but with `decode` in place of `serde_json`.
+	///
+	/// Trailing whitespace is removed before `decode` sees the body, since some servers and
+	/// proxies end responses with a newline that stricter parsers reject.
+	fn decode<T>(&self, decode: impl FnOnce(&[u8]) -> EdbResult<T>) -> EdbResult<T> {
+		let looks_like_html = self.html || self.bytes.trim_ascii_start().starts_with(b"<");
+		if !looks_like_html {
+			match decode(self.bytes.trim_ascii_end()) {
+				Ok(value) => return Ok(value),
+				Err(e) if self.status.is_success() => return Err(e),
+				Err(_) => {}
+			}
+		}
+		let end = self.bytes.len().min(200);
+		let snippet = String::from_utf8_lossy(&self.bytes[..end])
+			.trim()
+			.to_string();
+		Err(EdbErrorKind::NonJsonResponse(self.status.as_u16(), snippet).into())
+	}
+}
+
+/// A request body and the `Content-Type` to send it with.
+struct Payload<'a> {
+	bytes: Vec<u8>,
+	content_type: &'a str,
+}
+
+/// The longest body that [`exists`][EasyDB::exists] checks with `GET`, since it could be an
+/// empty string padded with whitespace.
+const HEAD_MAX_EMPTY: u64 = 16;
+
 impl EasyDB {
 	/// Creates an EasyDB using the `easydb.toml` in the current directory.
 	///
@@ -38,17 +237,206 @@ impl EasyDB {
 	///
 	/// # Example
 	///
-	/// ```
+	/// ```no_run
 	/// # use crate::easydb::{EasyDB, errors::EdbError};
 	/// let edb = EasyDB::new()?;
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
 	pub fn new() -> EdbResult<Self> {
-		let edb: Self = read_to_string("./easydb.toml")?.parse()?;
-		edb.validate_uuid()?;
+		Self::from_toml_path("./easydb.toml")
+	}
+	/// Creates an EasyDB using an `easydb.toml` from the platform's config directory, falling back
+	/// to the current directory.
+	///
+	/// The first file that exists is used, searching in this order:
+	///
+	/// 1. `$XDG_CONFIG_HOME/easydb/easydb.toml`, if `XDG_CONFIG_HOME` is set
+	/// 2. On Windows, `%APPDATA%\easydb\easydb.toml`
+	/// 3. On macOS, `~/Library/Application Support/easydb/easydb.toml`
+	/// 4. On platforms other than Windows, `~/.config/easydb/easydb.toml`
+	/// 5. `./easydb.toml`
+	///
+	/// # Errors
+	///
+	/// Will fail if none of the files exist, or for the same reasons as [`new`][EasyDB::new].
+	///
+	/// # Example
+	///
+	/// ```no_run
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// let edb = EasyDB::from_config_dir()?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn from_config_dir() -> EdbResult<Self> {
+		Self::from_config_dirs(&config_dirs())
+	}
+	/// Like [`from_config_dir`][EasyDB::from_config_dir], but searches `base/easydb/easydb.toml`
+	/// instead of the platform's config directories before falling back to `./easydb.toml`.
+	pub fn from_config_dir_in(base: impl AsRef<Path>) -> EdbResult<Self> {
+		Self::from_config_dirs(&[base.as_ref().to_path_buf()])
+	}
+	fn from_config_dirs(dirs: &[PathBuf]) -> EdbResult<Self> {
+		let path = dirs
+			.iter()
+			.map(|dir| dir.join("easydb").join("easydb.toml"))
+			.find(|path| path.is_file())
+			.unwrap_or_else(|| PathBuf::from("./easydb.toml"));
+		Self::from_toml_path(path)
+	}
+	/// Creates an EasyDB using the TOML file at `path`, in the same format as `easydb.toml`.
+	///
+	/// The same conversion is available as `EasyDB::try_from(path)`.
+	///
+	/// # Errors
+	///
+	/// Will fail if the file can't be read or parsed, or if the UUID doesn't form a valid URL.
+	///
+	/// # Example
+	///
+	/// ```no_run
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// let edb = EasyDB::from_toml_path("config/easydb.toml")?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn from_toml_path(path: impl AsRef<Path>) -> EdbResult<Self> {
+		let mut edb: Self = read_to_string(path)?.parse()?;
+		edb.validate_url()?;
+		Ok(edb)
+	}
+	/// Creates an EasyDB from TOML like [`parse`][EasyDB::from_str] does, but also applies the
+	/// optional settings below. Plain `parse` ignores them.
+	///
+	/// | Field        | Type    | Setting                                  |
+	/// |--------------|---------|------------------------------------------|
+	/// | `Timeout`    | seconds | [`timeout`][EasyDBBuilder::timeout]         |
+	/// | `MaxRetries` | integer | [`max_retries`][EasyDBBuilder::max_retries] |
+	/// | `RateLimit`  | per sec | [`rate_limit`][EasyDBBuilder::rate_limit]   |
+	/// | `ReadOnly`   | boolean | [`read_only`][EasyDBBuilder::read_only]     |
+	///
+	/// # Errors
+	///
+	/// Will fail if the TOML can't be parsed, if `Timeout` is negative or too large, or if the
+	/// UUID doesn't form a valid URL.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// let s = r#"
+	/// UUID = "abcd"
+	/// Token = "efgh"
+	/// Timeout = 2.5
+	/// MaxRetries = 3
+	/// ReadOnly = true
+	/// "#;
+	/// let edb = EasyDB::from_toml_str_full(s)?;
+	/// assert_eq!(edb.uuid(), "abcd");
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn from_toml_str_full(s: &str) -> EdbResult<Self> {
+		let config: FullConfig = toml::from_str(s)?;
+		let mut builder = EasyDB::builder().uuid(config.uuid).token(config.token);
+		if let Some(url) = config.url {
+			builder = builder.url(url);
+		}
+		if let Some(secs) = config.timeout {
+			let timeout = Duration::try_from_secs_f64(secs)
+				.map_err(|e| EdbError::message(format!("Invalid Timeout {}: {}", secs, e)))?;
+			builder = builder.timeout(timeout);
+		}
+		if let Some(retries) = config.max_retries {
+			builder = builder.max_retries(retries);
+		}
+		if let Some(rate) = config.rate_limit {
+			builder = builder.rate_limit(rate);
+		}
+		if let Some(read_only) = config.read_only {
+			builder = builder.read_only(read_only);
+		}
+		builder.build()
+	}
+	/// Creates an EasyDB backed by a private in-memory database instead of easydb.io.
+	///
+	/// Each call starts a small HTTP server on a random localhost port, with its own empty
+	/// database, in a background thread. The server stops once this handle and every handle
+	/// made from it, such as with [`to_builder`][EasyDB::to_builder], are dropped. Every method
+	/// works the same as against easydb.io, including a missing key reading as an empty string,
+	/// so this is meant for examples, doctests, and tests of code that uses this crate. It isn't
+	/// meant to store anything real: nothing is saved, and other processes on the machine can
+	/// connect to the port.
+	///
+	/// # Errors
+	///
+	/// Will fail if no localhost port can be opened, or if the HTTP client can't be created.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// let edb = EasyDB::in_memory()?;
+	/// edb.put("hello", "world")?;
+	/// assert_eq!(edb.get("hello")?, "world");
+	/// assert_eq!(edb.get("missing")?, "");
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn in_memory() -> EdbResult<Self> {
+		let server = mock::MockServer::start()?;
+		let mut edb = Self::builder()
+			.uuid(mock::UUID.to_string())
+			.token(mock::TOKEN.to_string())
+			.url(server.url().to_string())
+			.build()?;
+		edb.options.mock = Some(server.guard());
 		Ok(edb)
 	}
+	/// Creates an EasyDB using environment variables.
+	///
+	/// The UUID and token are read from `EASYDB_UUID` and `EASYDB_TOKEN`, and the URL from
+	/// `EASYDB_URL` if it's set. For the UUID and token, a `*_FILE` variable such as
+	/// `EASYDB_TOKEN_FILE` can name a file to read the value from instead, as with Docker and
+	/// Kubernetes secrets. The `*_FILE` variable takes precedence, and trailing whitespace in the
+	/// file is ignored.
+	///
+	/// # Errors
+	///
+	/// Will fail if the UUID or token isn't set, if a `*_FILE` can't be read, or for the same
+	/// reasons as [`from_uuid_token`][EasyDB::from_uuid_token].
+	///
+	/// # Example
+	///
+	/// ```no_run
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// // EASYDB_UUID=aaaa... EASYDB_TOKEN_FILE=/run/secrets/easydb_token
+	/// let edb = EasyDB::from_env()?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn from_env() -> EdbResult<Self> {
+		Self::from_vars(|name| env::var(name).ok().filter(|value| !value.is_empty()))
+	}
+	pub(crate) fn from_vars(var: impl Fn(&str) -> Option<String>) -> EdbResult<Self> {
+		let read = |name: &str| -> EdbResult<Option<String>> {
+			let file = format!("{}_FILE", name);
+			match var(&file) {
+				Some(path) => {
+					let value = read_to_string(&path)
+						.chain_err(|| format!("Couldn't read {} from {}", file, path))?;
+					Ok(Some(value.trim_end().to_string()))
+				}
+				None => Ok(var(name)),
+			}
+		};
+		let uuid = read("EASYDB_UUID")?
+			.ok_or_else(|| EdbError::message("EASYDB_UUID or EASYDB_UUID_FILE must be set"))?;
+		let token = read("EASYDB_TOKEN")?
+			.ok_or_else(|| EdbError::message("EASYDB_TOKEN or EASYDB_TOKEN_FILE must be set"))?;
+		Self::from_uuid_token(uuid, token, var("EASYDB_URL"))
+	}
 	/// Creates an EasyDB using a UUID, Token, and optional URL (defaults to
 	/// `https://app.easydb.io/database/`).
 	///
@@ -65,28 +453,224 @@ impl EasyDB {
 	/// ```
 	///
 	pub fn from_uuid_token(uuid: String, token: String, url: Option<String>) -> EdbResult<Self> {
-		let edb = Self {
+		Self::with_client(uuid, token, url, Client::new(), Options::default())
+	}
+	/// Creates an EasyDB from an already parsed URL and an existing HTTP client.
+	///
+	/// Unlike [`from_uuid_token`][EasyDB::from_uuid_token], the URL isn't converted back to a
+	/// string and parsed again. All other settings use their defaults; use
+	/// [`builder`][EasyDB::builder] to change them.
+	///
+	/// # Errors
+	///
+	/// Will fail if `uuid` can't be joined onto `url`. Calls made with a
+	/// [`RequestOptions::timeout`] fail later, because `client`'s settings can't be read back to
+	/// build a second client with another timeout.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use easydb::{EasyDB, Url, errors::EdbError};
+	/// let url: Url = "https://app.easydb.io/database/".parse()?;
+	/// let client = reqwest::Client::new();
+	/// let edb = EasyDB::from_parts("aaaa...".to_string(), "bbbb...".to_string(), url, client)?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn from_parts(uuid: String, token: String, url: Url, client: Client) -> EdbResult<Self> {
+		let options = Options {
+			client: ClientOptions {
+				custom: true,
+				..ClientOptions::default()
+			},
+			..Options::default()
+		};
+		Self::from_url(uuid, token, url, client, options)
+	}
+	/// Returns an [`EasyDBBuilder`] for configuring the HTTP client.
+	pub fn builder() -> EasyDBBuilder {
+		EasyDBBuilder::new()
+	}
+	/// Returns an [`EasyDBBuilder`] with the same credentials, URL, and settings as this
+	/// `EasyDB`, for creating a second handle that differs in a few settings.
+	///
+	/// The HTTP client is reused unless a setting that it's built from (such as the timeout) is
+	/// changed.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use std::time::Duration;
+	/// # use easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::from_uuid_token("aaaa...".to_string(), "bbbb...".to_string(), None)?;
+	/// let patient = edb.to_builder().timeout(Duration::from_secs(300)).build()?;
+	/// assert_eq!(patient.uuid(), edb.uuid());
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn to_builder(&self) -> EasyDBBuilder {
+		EasyDBBuilder::prefilled(
+			self.uuid.clone(),
+			self.token.clone(),
+			self.url.to_string(),
+			self.client.clone(),
+			self.options.clone(),
+		)
+	}
+	/// Returns a copy of the UUID, token, and URL. See [`EasyDBConfig`].
+	pub fn config(&self) -> EasyDBConfig {
+		EasyDBConfig {
+			uuid: self.uuid.clone(),
+			token: self.token.clone(),
+			url: self.url.to_string(),
+		}
+	}
+	/// Like [`config`][EasyDB::config], but consumes the `EasyDB` instead of copying.
+	pub fn into_config(self) -> EasyDBConfig {
+		EasyDBConfig {
+			uuid: self.uuid,
+			token: self.token,
+			url: self.url.into_string(),
+		}
+	}
+	/// Creates a handle to the same database that authenticates with `token` instead.
+	///
+	/// The new handle shares this one's HTTP client, so it reuses its connections, and keeps
+	/// every setting except [`fallback_tokens`][EasyDBBuilder::fallback_tokens]: those belong to
+	/// this handle's credential, so a rejected `token` is never retried with them. Nothing
+	/// about `token` is checked until a request is sent.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let tenant = edb.with_token("tenant-token".to_string());
+	/// assert_eq!(tenant.uuid(), edb.uuid());
+	/// assert_eq!(tenant.token(), "tenant-token");
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn with_token(&self, token: String) -> EasyDB {
+		EasyDB {
+			uuid: self.uuid.clone(),
+			token,
+			client: self.client.clone(),
+			url: self.url.clone(),
+			options: Options {
+				fallback_tokens: Vec::new(),
+				..self.options.clone()
+			},
+			written: Default::default(),
+			timeout_clients: self.timeout_clients.clone(),
+		}
+	}
+	/// Creates a handle to the same database at another base URL, such as a staging server or
+	/// a local mock.
+	///
+	/// The new handle shares this one's HTTP client and keeps its UUID, token, and every
+	/// setting. The URL is checked the same way as when building, including
+	/// [`trailing_slash`][EasyDBBuilder::trailing_slash].
+	///
+	/// # Errors
+	///
+	/// Will fail if `url` can't be parsed, or if the UUID doesn't form a valid URL with it.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let staging = edb.with_url("https://staging.example.com/database/".to_string())?;
+	/// assert_eq!(staging.uuid(), edb.uuid());
+	/// assert_eq!(staging.url(), "https://staging.example.com/database/");
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn with_url(&self, url: String) -> EdbResult<EasyDB> {
+		Self::from_url(
+			self.uuid.clone(),
+			self.token.clone(),
+			url.parse()?,
+			self.client.clone(),
+			self.options.clone(),
+		)
+	}
+	pub(crate) fn with_client(
+		uuid: String,
+		token: String,
+		url: Option<String>,
+		client: Client,
+		options: Options,
+	) -> EdbResult<Self> {
+		let url = match url {
+			Some(url) => url.parse()?,
+			None => default_url(),
+		};
+		Self::from_url(uuid, token, url, client, options)
+	}
+	fn from_url(
+		uuid: String,
+		token: String,
+		url: Url,
+		client: Client,
+		options: Options,
+	) -> EdbResult<Self> {
+		let mut edb = Self {
 			uuid,
 			token,
-			client: Client::new(),
-			url: url.unwrap_or_else(default_url),
+			client,
+			url,
+			options,
+			written: Default::default(),
+			timeout_clients: Default::default(),
 		};
-		edb.url.parse::<Url>()?;
-		edb.validate_uuid()?;
+		edb.validate_url()?;
 		Ok(edb)
 	}
-	fn validate_uuid(&self) -> EdbResult<()> {
-		self.url.parse::<Url>().unwrap().join(&self.uuid)?;
+	/// Applies [`trailing_slash`][EasyDBBuilder::trailing_slash], then checks that the UUID can
+	/// be joined onto the URL.
+	fn validate_url(&mut self) -> EdbResult<()> {
+		if !self.url.cannot_be_a_base() && !self.url.path().ends_with('/') {
+			match self.options.trailing_slash {
+				TrailingSlash::Strict => {
+					return Err(EdbErrorKind::MissingTrailingSlash(self.url.to_string()).into())
+				}
+				TrailingSlash::Lenient => {
+					edb_log!(
+						warn,
+						"URL {} doesn't end with a slash, so one was added",
+						self.url
+					);
+					let path = format!("{}/", self.url.path());
+					self.url.set_path(&path);
+				}
+			}
+		}
+		self.url.join(&self.uuid)?;
 		Ok(())
 	}
+	/// Applies [`trim_keys`][EasyDBBuilder::trim_keys].
+	fn normalize_key<'a>(&self, key: &'a str) -> &'a str {
+		if self.options.trim_keys {
+			key.trim()
+		} else {
+			key
+		}
+	}
 	fn create_key_url(&self, key: &str) -> EdbResult<Url> {
-		self.url
-			.parse::<Url>()
-			.unwrap()
+		let key = self.normalize_key(key);
+		let url = self
+			.url
 			.join(&format!("{}/", self.uuid))
 			.unwrap()
 			.join(key)
-			.chain_err(|| format!("Invalid key: {}", key))
+			.chain_err(|| format!("Invalid key: {}", key))?;
+		let max = self.options.max_key_url_len;
+		if url.as_str().len() > max {
+			return Err(EdbErrorKind::KeyTooLong(url.as_str().len(), max).into());
+		}
+		Ok(url)
 	}
 	/// Returns the stored UUID.
 	pub fn uuid(&self) -> &str {
@@ -98,184 +682,1926 @@ impl EasyDB {
 	}
 	/// Returns the stored URL.
 	pub fn url(&self) -> &str {
-		&self.url
+		self.url.as_str()
 	}
-
-	/// Gets the value associated with `key`.
+	/// Returns the HTTP client this handle sends its requests with, for sending unrelated
+	/// requests over the same connection pool and settings.
+	///
+	/// The client can't be changed through this reference; to use a different one, build a new
+	/// handle with [`from_parts`][EasyDB::from_parts]. A `Client` is cheap to clone, and clones
+	/// share the pool. Requests sent with it directly don't get the token, retries, or
+	/// [`rate_limit`][EasyDBBuilder::rate_limit].
+	pub fn client(&self) -> &Client {
+		&self.client
+	}
+	/// Returns the URL of the database itself: the stored URL joined with the UUID.
+	///
+	/// This is the URL that [`list`][EasyDB::list] and [`list_writer`][EasyDB::list_writer]
+	/// request, which is useful for logging or for calling the API with another tool.
+	///
+	/// # Errors
+	///
+	/// Will fail if the UUID can't be joined onto the URL. That's already checked when the
+	/// `EasyDB` is created, so this only fails for a handle that was deserialized without
+	/// validation.
 	///
 	/// # Example
 	///
 	/// ```
 	/// # use crate::easydb::{EasyDB, errors::EdbError};
-	/// # let edb = EasyDB::new()?;
-	/// let s = edb.get("somekey")?;
+	/// let edb = EasyDB::from_uuid_token("abcd".to_string(), "efgh".to_string(), None)?;
+	/// assert_eq!(edb.database_url()?, "https://app.easydb.io/database/abcd");
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
-	pub fn get(&self, key: &str) -> EdbResult<String> {
-		self.get_json(key)?
-			.as_str()
-			.map(|s| s.to_string())
-			.ok_or_else(|| "Value was not a string".into())
+	pub fn database_url(&self) -> EdbResult<String> {
+		Ok(self.database_url_parsed()?.into_string())
 	}
-	/// Gets the value associated with `key` in json format.
+	fn database_url_parsed(&self) -> EdbResult<Url> {
+		Ok(self.url.join(&self.uuid)?)
+	}
+
+	/// Gets the value associated with `key`.
+	///
+	/// A key that was never set or was deleted returns an empty string, or fails with
+	/// [`MissingKeyBehavior::Error`]. Use [`try_get`][EasyDB::try_get] to tell missing keys
+	/// apart.
 	///
 	/// # Example
 	///
 	/// ```
 	/// # use crate::easydb::{EasyDB, errors::EdbError};
-	/// # let edb = EasyDB::new()?;
-	/// let json = edb.get_json("somekey")?;
+	/// # let edb = EasyDB::in_memory()?;
+	/// let s = edb.get("somekey")?;
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
-	pub fn get_json(&self, key: &str) -> EdbResult<Json> {
-		let mut s = Vec::new();
-		self.get_writer(key, &mut s)?;
-		Ok(serde_json::from_slice(&s)?)
+	pub fn get(&self, key: impl AsRef<str>) -> EdbResult<String> {
+		self.get_with_opts(key, &RequestOptions::default())
 	}
-	/// Assigns `value` to `key` and returns the status code.
+	/// Like [`get`][EasyDB::get], but with the timeout or retries overridden for this call.
 	///
 	/// # Example
 	///
 	/// ```
-	/// # use crate::easydb::{EasyDB, errors::EdbError};
-	/// # let edb = EasyDB::new()?;
-	/// let status = edb.put("somekey", "somevalue")?;
+	/// # use std::time::Duration;
+	/// # use crate::easydb::{EasyDB, RequestOptions, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let opts = RequestOptions {
+	///     timeout: Some(Duration::from_secs(120)),
+	///     ..RequestOptions::default()
+	/// };
+	/// let s = edb.get_with_opts("somekey", &opts)?;
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
-	pub fn put(&self, key: &str, value: &str) -> EdbResult<u16> {
-		let new_value = json!(value);
-		self.put_json(key, new_value)
+	pub fn get_with_opts(&self, key: impl AsRef<str>, opts: &RequestOptions) -> EdbResult<String> {
+		let key = key.as_ref();
+		match self.get_json_inner(key, opts)? {
+			Some(Json::String(s)) => Ok(s),
+			Some(_) => Err(EdbError::message("Value was not a string")),
+			None if self.options.missing_key == MissingKeyBehavior::Error => {
+				Err(EdbErrorKind::MissingKey(key.to_string()).into())
+			}
+			None => Ok(String::new()),
+		}
 	}
-	/// Assigns a json `value` to `key` and returns the status code.
+	/// Gets the value associated with `key` in json format.
+	///
+	/// A missing key returns `Json::String("")`, whether the server signals it with an empty
+	/// string, a successful response with an empty body, or a 404 status. Set
+	/// [`missing_key_behavior`][EasyDBBuilder::missing_key_behavior] to return `Json::Null` or
+	/// fail instead.
 	///
 	/// # Example
 	///
 	/// ```
 	/// # use crate::easydb::{EasyDB, errors::EdbError};
-	/// # use serde_json::json;
-	/// # let edb = EasyDB::new()?;
-	/// let status = edb.put_json("somekey", json!({"a": "b"}))?;
+	/// # let edb = EasyDB::in_memory()?;
+	/// let json = edb.get_json("somekey")?;
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
-	pub fn put_json(&self, key: &str, value: Json) -> EdbResult<u16> {
-		let body = json!({ "value": value }).to_string();
-		Ok(self
-			.client
-			.post(self.create_key_url(key)?)
-			.header(CONTENT_TYPE, "application/json")
-			.header(CONTENT_LENGTH, body.len())
-			.header("token", &self.token)
-			.body(body)
-			.send()?
-			.status()
-			.as_u16())
+	pub fn get_json(&self, key: impl AsRef<str>) -> EdbResult<Json> {
+		self.get_json_with_opts(key, &RequestOptions::default())
+	}
+	/// Like [`get_json`][EasyDB::get_json], but with the timeout or retries overridden for this
+	/// call.
+	pub fn get_json_with_opts(
+		&self,
+		key: impl AsRef<str>,
+		opts: &RequestOptions,
+	) -> EdbResult<Json> {
+		let key = key.as_ref();
+		match self.get_json_inner(key, opts)? {
+			Some(value) => Ok(value),
+			None => match self.options.missing_key {
+				MissingKeyBehavior::EmptyString => Ok(json!("")),
+				MissingKeyBehavior::Null => Ok(Json::Null),
+				MissingKeyBehavior::Error => Err(EdbErrorKind::MissingKey(key.to_string()).into()),
+			},
+		}
 	}
-	/// Deletes the value associated with `key` and returns the status code.
+	/// Gets the value of `key`, or `None` if it's missing or set to an empty string.
+	fn get_json_inner(&self, key: &str, opts: &RequestOptions) -> EdbResult<Option<Json>> {
+		let body = self.fetch(Some(key), opts)?;
+		let missing = body.status == StatusCode::NOT_FOUND
+			|| (body.bytes.trim_ascii().is_empty() && body.status.is_success());
+		let value = if missing {
+			json!("")
+		} else {
+			self.verify(key, self.decode_value(&body)?)?
+		};
+		self.check_stale(key, &value)?;
+		Ok(if value == "" { None } else { Some(value) })
+	}
+	/// Like [`get`][EasyDB::get], but returns `None` if `key` is missing.
+	///
+	/// easydb.io doesn't distinguish a missing key from one set to an empty string, so both
+	/// return `None`.
 	///
 	/// # Example
 	///
 	/// ```
 	/// # use crate::easydb::{EasyDB, errors::EdbError};
-	/// # let edb = EasyDB::new()?;
-	/// let status = edb.delete("somekey")?;
+	/// # let edb = EasyDB::in_memory()?;
+	/// match edb.try_get("somekey")? {
+	///     Some(s) => println!("Found {}", s),
+	///     None => println!("Not set"),
+	/// }
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
-	pub fn delete(&self, key: &str) -> EdbResult<u16> {
-		Ok(self
-			.client
-			.delete(self.create_key_url(key)?)
-			.header(CONTENT_TYPE, "application/json")
-			.header("token", &self.token)
-			.send()?
-			.status()
-			.as_u16())
+	pub fn try_get(&self, key: impl AsRef<str>) -> EdbResult<Option<String>> {
+		match self.try_get_json(key)? {
+			Some(Json::String(s)) => Ok(Some(s)),
+			Some(_) => Err(EdbError::message("Value was not a string")),
+			None => Ok(None),
+		}
 	}
-	/// Returns a `HashMap<String, String>` of all the data in this database.
+	/// Like [`get_json`][EasyDB::get_json], but returns `None` if `key` is missing.
 	///
-	/// # Errors
+	/// As with [`try_get`][EasyDB::try_get], a key set to an empty string also returns `None`.
+	pub fn try_get_json(&self, key: impl AsRef<str>) -> EdbResult<Option<Json>> {
+		self.get_json_inner(key.as_ref(), &RequestOptions::default())
+	}
+	/// Like [`get`][EasyDB::get], but returns `default` if `key` is missing.
 	///
-	/// Will fail if any of the values are not strings.
+	/// Only a missing key (or one set to an empty string) is replaced by `default`; network and
+	/// server errors are still returned.
 	///
 	/// # Example
 	///
 	/// ```
 	/// # use crate::easydb::{EasyDB, errors::EdbError};
-	/// # let edb = EasyDB::new()?;
-	/// let map = edb.list()?;
+	/// # let edb = EasyDB::in_memory()?;
+	/// let theme = edb.get_or("theme", "light")?;
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
-	pub fn list(&self) -> EdbResult<HashMap<String, String>> {
-		self.list_json()?
-			.drain()
-			.map(|(s, v)| match v.as_str() {
-				Some(v_str) => Ok((s, v_str.to_string())),
-				None => Err(format!("A value was not a string: key: {}, value: {}", s, v).into()),
-			})
-			.collect()
+	pub fn get_or(&self, key: impl AsRef<str>, default: &str) -> EdbResult<String> {
+		Ok(self.try_get(key)?.unwrap_or_else(|| default.to_string()))
 	}
-	/// Returns a `HashMap<String, Json>` of all the data in this database.
+	/// Like [`get_json`][EasyDB::get_json], but returns `default` if `key` is missing.
+	///
+	/// As with [`get_or`][EasyDB::get_or], errors are still returned.
 	///
 	/// # Example
 	///
 	/// ```
+	/// # use serde_json::json;
 	/// # use crate::easydb::{EasyDB, errors::EdbError};
-	/// # let edb = EasyDB::new()?;
-	/// let map = edb.list()?;
+	/// # let edb = EasyDB::in_memory()?;
+	/// let limits = edb.get_json_or("limits", json!({"max": 10}))?;
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
-	pub fn list_json(&self) -> EdbResult<HashMap<String, Json>> {
-		let mut s = Vec::new();
-		self.list_writer(&mut s)?;
-		Ok(serde_json::from_slice(&s)?)
+	pub fn get_json_or(&self, key: impl AsRef<str>, default: Json) -> EdbResult<Json> {
+		Ok(self.try_get_json(key)?.unwrap_or(default))
 	}
-	/// Clears the database.
+	/// Gets a boolean value, or `None` if `key` is missing.
+	///
+	/// # Errors
+	///
+	/// Will fail if the value isn't `true` or `false`.
 	///
 	/// # Example
 	///
 	/// ```
 	/// # use crate::easydb::{EasyDB, errors::EdbError};
-	/// # let edb = EasyDB::new()?;
-	/// edb.clear()?;
-	/// # std::thread::sleep(std::time::Duration::from_secs(1));
-	/// assert_eq!(edb.list()?.len(), 0);
+	/// # let edb = EasyDB::in_memory()?;
+	/// let enabled = edb.get_bool("feature-enabled")?.unwrap_or(false);
 	/// # Ok::<(), EdbError>(())
 	/// ```
 	///
-	pub fn clear(&self) -> EdbResult<()> {
-		let map = self.list_json()?;
-		self.clear_keys(map.keys().map(|k| &k[..]))?;
-		Ok(())
+	pub fn get_bool(&self, key: impl AsRef<str>) -> EdbResult<Option<bool>> {
+		self.get_typed(key, "a bool", Json::as_bool)
 	}
-	fn clear_keys<'a, I>(&self, keys: I) -> EdbResult<()>
-	where
-		I: Iterator<Item = &'a str>,
-	{
-		for key in keys {
-			self.delete(key)?;
-		}
-		Ok(())
+	/// Gets an integer value, or `None` if `key` is missing.
+	///
+	/// # Errors
+	///
+	/// Will fail if the value isn't a number without a fractional part that fits in an `i64`.
+	pub fn get_i64(&self, key: impl AsRef<str>) -> EdbResult<Option<i64>> {
+		self.get_typed(key, "an i64", Json::as_i64)
 	}
-	/// An alternative to `get()` that works with a writer. Fetches data associated with `key` and
-	/// writes into `value`, returning the status code.
+	/// Gets a numeric value, or `None` if `key` is missing. Integers are converted, so `3`
+	/// reads as `3.0`.
 	///
-	/// The response should have the value that was originally set in JSON form. If the key was
-	/// never set, was deleted, or has no data, the response will be an empty string: `""`.
-	pub fn get_writer<W>(&self, key: &str, value: &mut W) -> EdbResult<u16>
-	where
-		W: Write,
+	/// # Errors
+	///
+	/// Will fail if the value isn't a number.
+	pub fn get_f64(&self, key: impl AsRef<str>) -> EdbResult<Option<f64>> {
+		self.get_typed(key, "a number", Json::as_f64)
+	}
+	/// Gets a string value, or `None` if `key` is missing. The same as
+	/// [`try_get`][EasyDB::try_get], named to match the other typed getters.
+	///
+	/// # Errors
+	///
+	/// Will fail if the value isn't a string.
+	pub fn get_string(&self, key: impl AsRef<str>) -> EdbResult<Option<String>> {
+		self.try_get(key)
+	}
+	fn get_typed<T>(
+		&self,
+		key: impl AsRef<str>,
+		expected: &str,
+		convert: impl FnOnce(&Json) -> Option<T>,
+	) -> EdbResult<Option<T>> {
+		match self.try_get_json(key)? {
+			Some(value) => match convert(&value) {
+				Some(value) => Ok(Some(value)),
+				None => Err(EdbError::message(format!("Value was not {}", expected))),
+			},
+			None => Ok(None),
+		}
+	}
+	/// Gets the value associated with `key` as a JSON object.
+	///
+	/// # Errors
+	///
+	/// Will fail if the value isn't an object. A missing key reads as an empty string, so it
+	/// fails too; use [`try_get_json`][EasyDB::try_get_json] to tell the two apart.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// # edb.put_json("user-1", serde_json::json!({"name": "Ada"}))?;
+	/// let user = edb.get_object("user-1")?;
+	/// println!("{}", user["name"]);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn get_object(&self, key: impl AsRef<str>) -> EdbResult<Map<String, Json>> {
+		match self.get_json(key)? {
+			Json::Object(map) => Ok(map),
+			_ => Err(EdbError::message("Value was not an object")),
+		}
+	}
+	/// Returns `true` if `key` is set to something other than an empty string.
+	///
+	/// This sends a `HEAD` request first, so a large value isn't downloaded just to check for
+	/// it. Only a `404` from `HEAD`, or a successful one whose `Content-Length` is too long to
+	/// be an empty string, is trusted. Anything else, including a server that doesn't support
+	/// `HEAD` (`405` or `501`), a compressed response, or a body short enough to be `""`, is
+	/// checked again with `GET`, so a missing key can cost two requests. With
+	/// [`checksums`][EasyDBBuilder::checksums] enabled, only `GET` is used, because an empty
+	/// string is stored with its checksum.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// if !edb.exists("somekey")? {
+	///     edb.put("somekey", "default")?;
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn exists(&self, key: impl AsRef<str>) -> EdbResult<bool> {
+		let key = key.as_ref();
+		if !self.options.checksums {
+			let resp =
+				self.request_with_opts(Method::HEAD, Some(key), None, &RequestOptions::default())?;
+			let len = resp
+				.headers()
+				.get(CONTENT_LENGTH)
+				.and_then(|len| len.to_str().ok())
+				.and_then(|len| len.parse::<u64>().ok());
+			let compressed = resp.headers().contains_key(CONTENT_ENCODING);
+			match (resp.status(), len) {
+				(StatusCode::NOT_FOUND, _) => return Ok(false),
+				(status, Some(len))
+					if status.is_success() && !compressed && len > HEAD_MAX_EMPTY =>
+				{
+					return Ok(true)
+				}
+				_ => {}
+			}
+		}
+		Ok(self.try_get_json(key)?.is_some())
+	}
+	/// Watches `key` by polling it every `interval` from a background thread, and sends its new
+	/// value over the returned channel whenever it changes.
+	///
+	/// The current value is read before this returns and isn't sent; only later changes are.
+	/// Because this polls, a value that is overwritten again before the next poll is never seen,
+	/// and a key that changes and changes back looks unchanged. A deleted key is sent the way
+	/// [`get_json`][EasyDB::get_json] returns it, which is `""` by default. Failed polls are
+	/// skipped, and so are polls of a missing key with [`MissingKeyBehavior::Error`].
+	///
+	/// The poller runs until the [`WatchHandle`] is dropped or the receiver is.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the first read fails.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # use std::time::Duration;
+	/// # let edb = EasyDB::in_memory()?;
+	/// let (changes, _handle) = edb.watch_channel("somekey", Duration::from_secs(5))?;
+	/// # edb.put("somekey", "new")?;
+	/// for value in changes.iter().take(1) {
+	///     println!("somekey is now {}", value);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn watch_channel(
+		&self,
+		key: impl Into<String>,
+		interval: Duration,
+	) -> EdbResult<(Receiver<Json>, WatchHandle)> {
+		let key = key.into();
+		let current = self.get_json(&key)?;
+		// The poller gets its own record of writes, since it never writes and other writers'
+		// changes are exactly what it is looking for.
+		let poller = EasyDB {
+			uuid: self.uuid.clone(),
+			token: self.token.clone(),
+			client: self.client.clone(),
+			url: self.url.clone(),
+			options: self.options.clone(),
+			written: Default::default(),
+			timeout_clients: self.timeout_clients.clone(),
+		};
+		Ok(watch::spawn(poller, key, interval, current))
+	}
+	/// Gets the values of `keys`, sending one request per key. Missing keys are left out of the
+	/// result.
+	///
+	/// This costs one round trip per key but never transfers more than the requested values.
+	/// When asking for a large share of a small database,
+	/// [`get_many_via_list`][EasyDB::get_many_via_list] is usually faster.
+	///
+	/// # Errors
+	///
+	/// Stops at the first failed request.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let values = edb.get_many(&["a", "b"])?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn get_many<I>(&self, keys: I) -> EdbResult<HashMap<String, Json>>
+	where
+		I: IntoIterator,
+		I::Item: AsRef<str>,
+	{
+		let mut values = HashMap::new();
+		for key in keys {
+			let key = key.as_ref();
+			if let Some(value) = self.try_get_json(key)? {
+				values.insert(key.to_string(), value);
+			}
+		}
+		Ok(values)
+	}
+	/// Like [`get_many`][EasyDB::get_many], but fetches the whole database with a single
+	/// [`list_json`][EasyDB::list_json] and keeps only `keys`.
+	///
+	/// This is one round trip no matter how many keys are requested, but it transfers every
+	/// value in the database, so it's only cheaper while the database is small compared to the
+	/// number of keys wanted.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let values = edb.get_many_via_list(&["a", "b"])?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn get_many_via_list<I>(&self, keys: I) -> EdbResult<HashMap<String, Json>>
+	where
+		I: IntoIterator,
+		I::Item: AsRef<str>,
+	{
+		let mut all = self.list_json()?;
+		Ok(keys
+			.into_iter()
+			.filter_map(|key| {
+				let key = key.as_ref();
+				let value = all.remove(self.normalize_key(key)).filter(|v| v != "")?;
+				Some((key.to_string(), value))
+			})
+			.collect())
+	}
+	/// Assigns `value` to `key` and returns the outcome.
+	///
+	/// `value` is always stored as a JSON string, even if it contains JSON text. To store JSON
+	/// text as the value it describes, use [`put_raw_json`][EasyDB::put_raw_json].
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let status = edb.put("somekey", "somevalue")?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put(&self, key: impl AsRef<str>, value: &str) -> EdbResult<WriteOutcome> {
+		self.put_with_opts(key, value, &RequestOptions::default())
+	}
+	/// Like [`put`][EasyDB::put], but also returns `true` if `key` didn't exist beforehand.
+	///
+	/// easydb.io doesn't say whether a write created or replaced a value, so this checks with
+	/// [`exists`][EasyDB::exists] first, costing an extra read. Another client writing `key`
+	/// between the read and the write can make the answer wrong.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let (created, outcome) = edb.put_upsert("somekey", "somevalue")?;
+	/// println!("{} ({})", if created { "Created" } else { "Updated" }, outcome);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put_upsert(&self, key: impl AsRef<str>, value: &str) -> EdbResult<(bool, WriteOutcome)> {
+		let key = key.as_ref();
+		let created = !self.exists(key)?;
+		Ok((created, self.put(key, value)?))
+	}
+	/// Like [`put`][EasyDB::put], but with the timeout or retries overridden for this call.
+	pub fn put_with_opts(
+		&self,
+		key: impl AsRef<str>,
+		value: &str,
+		opts: &RequestOptions,
+	) -> EdbResult<WriteOutcome> {
+		let new_value = json!(value);
+		self.put_json_with_opts(key, new_value, opts)
+	}
+	/// Assigns a json `value` to `key` and returns the outcome.
+	///
+	/// `value` can be a [`Json`] or anything else that implements `Serialize`. A `&str` or
+	/// `String` is stored as a JSON string, the same as with [`put`][EasyDB::put].
+	///
+	/// # Errors
+	///
+	/// Will fail if `value` can't be represented as JSON (e.g. a map with non-string keys).
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # use serde_json::json;
+	/// # let edb = EasyDB::in_memory()?;
+	/// let status = edb.put_json("somekey", json!({"a": "b"}))?;
+	/// let status = edb.put_json("otherkey", vec![1, 2, 3])?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put_json(&self, key: impl AsRef<str>, value: impl Serialize) -> EdbResult<WriteOutcome> {
+		self.put_json_with_opts(key, value, &RequestOptions::default())
+	}
+	/// Assigns a JSON object to `key` and returns the outcome.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let mut user = serde_json::Map::new();
+	/// user.insert("name".to_string(), "Ada".into());
+	/// edb.put_object("user-1", user)?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put_object(
+		&self,
+		key: impl AsRef<str>,
+		object: Map<String, Json>,
+	) -> EdbResult<WriteOutcome> {
+		self.put_json(key, Json::Object(object))
+	}
+	/// Stores a boolean, which reads back with [`get_bool`][EasyDB::get_bool]. Unlike
+	/// `put(key, "true")`, this stores the JSON value `true` rather than a string.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// edb.put_bool("feature-enabled", true)?;
+	/// assert_eq!(edb.get_bool("feature-enabled")?, Some(true));
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put_bool(&self, key: impl AsRef<str>, value: bool) -> EdbResult<WriteOutcome> {
+		self.put_json(key, Json::Bool(value))
+	}
+	/// Stores an integer, which reads back with [`get_i64`][EasyDB::get_i64].
+	pub fn put_i64(&self, key: impl AsRef<str>, value: i64) -> EdbResult<WriteOutcome> {
+		self.put_json(key, Json::from(value))
+	}
+	/// Stores a number, which reads back with [`get_f64`][EasyDB::get_f64].
+	///
+	/// # Errors
+	///
+	/// Will fail without sending a request if `value` is infinite or NaN, which JSON can't
+	/// represent.
+	pub fn put_f64(&self, key: impl AsRef<str>, value: f64) -> EdbResult<WriteOutcome> {
+		match serde_json::Number::from_f64(value) {
+			Some(number) => self.put_json(key, Json::Number(number)),
+			None => Err(EdbError::message(format!(
+				"{} can't be stored as JSON",
+				value
+			))),
+		}
+	}
+	/// Appends `item` to the array stored at `key` and returns the outcome of writing it back.
+	///
+	/// A missing key is treated as an empty array, so the first append creates a one-item array.
+	///
+	/// This isn't atomic: the array is read, changed locally, and written back with its own
+	/// request, so items appended by someone else in between are lost. Don't use it for keys
+	/// that several clients append to at once.
+	///
+	/// # Errors
+	///
+	/// Will fail if the existing value isn't an array.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # use serde_json::json;
+	/// # let edb = EasyDB::in_memory()?;
+	/// edb.append_to_array("log", json!({"event": "login"}))?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn append_to_array(&self, key: impl AsRef<str>, item: Json) -> EdbResult<WriteOutcome> {
+		let key = key.as_ref();
+		let mut items = match self.try_get_json(key)? {
+			None => Vec::new(),
+			Some(Json::Array(items)) => items,
+			Some(_) => return Err(EdbError::message("Value was not an array")),
+		};
+		items.push(item);
+		self.put_json(key, items)
+	}
+	/// Parses `json` as JSON text and assigns the value it describes to `key`.
+	///
+	/// This is for JSON that is already serialized: `put_raw_json(key, r#"{"a": 1}"#)` stores an
+	/// object, while [`put`][EasyDB::put] with the same text stores a string containing it.
+	///
+	/// # Errors
+	///
+	/// Will fail without sending a request if `json` isn't valid JSON.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// edb.put_raw_json("somekey", r#"{"a": [1, 2]}"#)?;
+	/// assert_eq!(edb.get_json("somekey")?["a"][1], 2);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put_raw_json(&self, key: impl AsRef<str>, json: &str) -> EdbResult<WriteOutcome> {
+		let value: Json = serde_json::from_str(json)?;
+		self.put_json(key, value)
+	}
+	/// Like [`put_json`][EasyDB::put_json], but with the timeout or retries overridden for this
+	/// call.
+	pub fn put_json_with_opts(
+		&self,
+		key: impl AsRef<str>,
+		value: impl Serialize,
+		opts: &RequestOptions,
+	) -> EdbResult<WriteOutcome> {
+		let key = key.as_ref();
+		let value = serde_json::to_value(value)?;
+		let body = if self.options.checksums {
+			json!({ "value": checksum::wrap(value.clone()) })
+		} else {
+			json!({ "value": &value })
+		};
+		let body = match &self.options.codec {
+			Some(codec) => codec.encode(&body)?,
+			None if self.options.pretty_bodies => serde_json::to_string_pretty(&body)?,
+			None => body.to_string(),
+		};
+		let outcome = self.write_outcome(self.request_with_opts(
+			Method::POST,
+			Some(key),
+			Some(body),
+			opts,
+		)?);
+		self.record_write(key, outcome, value);
+		Ok(outcome)
+	}
+	/// Stores `bytes` under `key` as an `application/octet-stream` body, without wrapping them in
+	/// JSON, and returns the outcome.
+	///
+	/// easydb.io itself only accepts JSON bodies, so this only works against a compatible
+	/// server or proxy that stores the request body as-is and returns it unchanged from a `GET`.
+	/// Read the bytes back with [`get_raw_bytes`][EasyDB::get_raw_bytes].
+	///
+	/// # Example
+	///
+	/// ```no_run
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// let edb = EasyDB::builder()
+	///     .uuid("aaaa...".to_string())
+	///     .token("bbbb...".to_string())
+	///     .url("https://blobs.example.com/database/".to_string())
+	///     .build()?;
+	/// edb.put_raw_bytes("avatar", &[0x89, b'P', b'N', b'G'])?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put_raw_bytes(&self, key: impl AsRef<str>, bytes: &[u8]) -> EdbResult<WriteOutcome> {
+		let key = key.as_ref();
+		let payload = Payload {
+			bytes: bytes.to_vec(),
+			content_type: "application/octet-stream",
+		};
+		let resp = self.request_payload(
+			Method::POST,
+			Some(key),
+			Some(payload),
+			&RequestOptions::default(),
+		)?;
+		if self.options.detect_stale_reads {
+			// The last JSON value written no longer applies.
+			let key = self.normalize_key(key);
+			self.written.lock().unwrap().remove(key);
+		}
+		Ok(self.write_outcome(resp))
+	}
+	/// Returns the body stored under `key` exactly as the server sends it, for values written
+	/// with [`put_raw_bytes`][EasyDB::put_raw_bytes].
+	///
+	/// A 404 status returns no bytes, the same as a missing key elsewhere.
+	///
+	/// # Errors
+	///
+	/// Fails with [`BadStatus`][crate::errors::EdbErrorKind::BadStatus] for any other non-2xx
+	/// status.
+	pub fn get_raw_bytes(&self, key: impl AsRef<str>) -> EdbResult<Vec<u8>> {
+		let body = self.fetch(Some(key.as_ref()), &RequestOptions::default())?;
+		match body.status {
+			StatusCode::NOT_FOUND => Ok(Vec::new()),
+			status if status.is_success() => Ok(body.bytes),
+			status => Err(EdbError::bad_status(
+				status.as_u16(),
+				status
+					.canonical_reason()
+					.unwrap_or("Unknown status")
+					.to_string(),
+			)),
+		}
+	}
+	/// Deletes the value associated with `key` and returns the outcome.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let status = edb.delete("somekey")?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn delete(&self, key: impl AsRef<str>) -> EdbResult<WriteOutcome> {
+		let key = key.as_ref();
+		let outcome = self.write_outcome(self.raw_request(Method::DELETE, Some(key), None)?);
+		self.record_write(key, outcome, json!(""));
+		Ok(outcome)
+	}
+	fn write_outcome(&self, resp: Response) -> WriteOutcome {
+		resp.status().as_u16().into()
+	}
+	/// Remembers `value` for stale read detection if the write succeeded.
+	fn record_write(&self, key: &str, outcome: WriteOutcome, value: Json) {
+		// A dry run never writes, so reads would always look stale.
+		if self.options.detect_stale_reads && !self.options.dry_run && outcome.is_success() {
+			let key = self.normalize_key(key).to_string();
+			self.written.lock().unwrap().insert(key, value);
+		}
+	}
+	/// Applies [`checksums`][EasyDBBuilder::checksums] to a value read from `key`. An empty
+	/// string is how the server reports a missing key, so it's passed through.
+	fn verify(&self, key: &str, value: Json) -> EdbResult<Json> {
+		if self.options.checksums && value != "" {
+			checksum::unwrap(key, value)
+		} else {
+			Ok(value)
+		}
+	}
+	/// Fails if `value` differs from the last value written to `key`, and forgets `key` once the
+	/// written value has been read back.
+	fn check_stale(&self, key: &str, value: &Json) -> EdbResult<()> {
+		if !self.options.detect_stale_reads {
+			return Ok(());
+		}
+		let key = self.normalize_key(key);
+		let mut written = self.written.lock().unwrap();
+		match written.get(key) {
+			Some(expected) if expected != value => {
+				Err(EdbErrorKind::StaleRead(key.to_string(), value.clone()).into())
+			}
+			Some(_) => {
+				written.remove(key);
+				Ok(())
+			}
+			None => Ok(()),
+		}
+	}
+	/// Returns a `HashMap<String, String>` of all the data in this database.
+	///
+	/// # Errors
+	///
+	/// Will fail if any of the values are not strings.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let map = edb.list()?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn list(&self) -> EdbResult<HashMap<String, String>> {
+		self.list_json()?
+			.drain()
+			.map(|(s, v)| match v.as_str() {
+				Some(v_str) => Ok((s, v_str.to_string())),
+				None => Err(EdbError::message(format!(
+					"A value was not a string: key: {}, value: {}",
+					s, v
+				))),
+			})
+			.collect()
+	}
+	/// Like [`list`][EasyDB::list], but leaves out values that aren't strings instead of failing.
+	///
+	/// The keys that were left out are appended to `skipped` in sorted order, so a database with
+	/// mixed values can still be read without losing track of what was missed.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let mut skipped = Vec::new();
+	/// let map = edb.list_strings_lossy(&mut skipped)?;
+	/// if !skipped.is_empty() {
+	///     eprintln!("Not strings: {:?}", skipped);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn list_strings_lossy(
+		&self,
+		skipped: &mut Vec<String>,
+	) -> EdbResult<HashMap<String, String>> {
+		let (strings, mut others) = split_strings(self.list_json()?);
+		skipped.append(&mut others);
+		Ok(strings)
+	}
+	/// Like [`list_strings_lossy`][EasyDB::list_strings_lossy], but returns the keys of values
+	/// that aren't strings, in sorted order, alongside the strings.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let (strings, skipped) = edb.try_list()?;
+	/// println!("{} strings, {} other values", strings.len(), skipped.len());
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn try_list(&self) -> EdbResult<(HashMap<String, String>, Vec<String>)> {
+		Ok(split_strings(self.list_json()?))
+	}
+	/// Returns a `HashMap<String, Json>` of all the data in this database.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let map = edb.list()?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn list_json(&self) -> EdbResult<HashMap<String, Json>> {
+		self.list_json_with_opts(&RequestOptions::default())
+	}
+	/// Like [`list_json`][EasyDB::list_json], but with `opts` overriding the handle's settings
+	/// for this call.
+	pub fn list_json_with_opts(&self, opts: &RequestOptions) -> EdbResult<HashMap<String, Json>> {
+		let body = self.fetch(None, opts)?;
+		let map: HashMap<String, Json> = if self.options.codec.is_none() {
+			body.parse()?
+		} else {
+			self.decode_object(&body)?.into_iter().collect()
+		};
+		if !self.options.checksums {
+			return Ok(map);
+		}
+		map.into_iter()
+			.map(|(key, value)| {
+				let value = self.verify(&key, value)?;
+				Ok((key, value))
+			})
+			.collect()
+	}
+	/// Returns all the data in this database in a `BTreeMap`, so iterating over it visits keys in
+	/// sorted order.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// for (key, value) in edb.list_sorted()? {
+	///     println!("{}: {}", key, value);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn list_sorted(&self) -> EdbResult<BTreeMap<String, Json>> {
+		Ok(self.list_json()?.into_iter().collect())
+	}
+	/// Like [`list_sorted`][EasyDB::list_sorted], but with `String` values like
+	/// [`list`][EasyDB::list].
+	///
+	/// # Errors
+	///
+	/// Will fail if any of the values are not strings.
+	pub fn list_sorted_strings(&self) -> EdbResult<BTreeMap<String, String>> {
+		Ok(self.list()?.into_iter().collect())
+	}
+	/// Returns every entry with its value deserialized into `T`, sorted by key.
+	///
+	/// # Errors
+	///
+	/// Will fail if any value can't be deserialized into `T`. The error names the key, and its
+	/// cause is the [`FromJson`][EdbErrorKind::FromJson] error.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use serde::Deserialize;
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// #[derive(Deserialize)]
+	/// struct User {
+	///     name: String,
+	/// }
+	///
+	/// for (key, user) in edb.get_all_typed::<User>()? {
+	///     println!("{}: {}", key, user.name);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn get_all_typed<T: DeserializeOwned>(&self) -> EdbResult<Vec<(String, T)>> {
+		self.list_sorted()?
+			.into_iter()
+			.map(|(key, value)| {
+				let value = serde_json::from_value(value)
+					.chain_err(|| format!("Value of key {} has the wrong type", key))?;
+				Ok((key, value))
+			})
+			.collect()
+	}
+	/// Returns up to `limit` entries, in sorted key order, starting `offset` entries in.
+	///
+	/// easydb.io has no paging of its own, so this fetches the whole database with
+	/// [`list_json`][EasyDB::list_json] and slices it locally. Each call costs a full fetch, and
+	/// changes between calls can shift entries from one page to another. To show several pages,
+	/// use [`paged`][EasyDB::paged], which fetches once and pages through the snapshot.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let second_page = edb.list_page(20, 20)?;
+	/// for (key, value) in second_page {
+	///     println!("{}: {}", key, value);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn list_page(&self, offset: usize, limit: usize) -> EdbResult<Vec<(String, Json)>> {
+		Ok(self
+			.list_sorted()?
+			.into_iter()
+			.skip(offset)
+			.take(limit)
+			.collect())
+	}
+	/// Fetches the whole database once and returns a view that splits it into pages of `limit`
+	/// entries, in sorted key order. See [`PagedView`].
+	pub fn paged(&self, limit: usize) -> EdbResult<PagedView> {
+		Ok(PagedView::new(
+			self.list_sorted()?.into_iter().collect(),
+			limit,
+		))
+	}
+	/// Fetches the whole database and returns an iterator over its keys and values.
+	///
+	/// This is a snapshot: everything is fetched up front with [`list_json`][EasyDB::list_json],
+	/// so later changes to the database aren't seen, and keys come in no particular order.
+	///
+	/// `EasyDB` deliberately doesn't implement `IntoIterator`, because `for (k, v) in &edb`
+	/// would have no way to report a failed request other than panicking. Iterate over
+	/// `edb.iter()?` instead.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// for (key, value) in edb.iter()? {
+	///     println!("{}: {}", key, value);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn iter(&self) -> EdbResult<hash_map::IntoIter<String, Json>> {
+		Ok(self.list_json()?.into_iter())
+	}
+	/// Returns all the data in this database in the order the server sent it.
+	///
+	/// Unlike [`list_json`][EasyDB::list_json], which returns a `HashMap` with an unpredictable
+	/// iteration order, this keeps the order of the top-level keys. Objects nested inside values
+	/// have their keys sorted.
+	///
+	/// With a [`json_codec`][EasyDBBuilder::json_codec], the values come from the codec, but the
+	/// order is still read from the body with `serde_json`. So this fails if the codec accepts a
+	/// body that isn't JSON.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// for (key, value) in edb.list_ordered()? {
+	///     println!("{}: {}", key, value);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn list_ordered(&self) -> EdbResult<Vec<(String, Json)>> {
+		let body = self.fetch(None, &RequestOptions::default())?;
+		let entries = if self.options.codec.is_none() {
+			let entries: OrderedEntries = body.parse()?;
+			entries.0
+		} else {
+			let mut map = self.decode_object(&body)?;
+			let order: OrderedEntries<IgnoredAny> = body.parse()?;
+			let mut entries: Vec<_> = order
+				.0
+				.into_iter()
+				.filter_map(|(key, _)| map.remove(&key).map(|value| (key, value)))
+				.collect();
+			// Keys the codec added, if any, go last.
+			entries.extend(map);
+			entries
+		};
+		if !self.options.checksums {
+			return Ok(entries);
+		}
+		entries
+			.into_iter()
+			.map(|(key, value)| {
+				let value = self.verify(&key, value)?;
+				Ok((key, value))
+			})
+			.collect()
+	}
+	/// Returns every entry as it's stored, parsed with `serde_json` and in the server's order.
+	fn list_stored(&self) -> EdbResult<Vec<(String, Json)>> {
+		let entries: OrderedEntries = self.fetch(None, &RequestOptions::default())?.parse()?;
+		Ok(entries.0)
+	}
+	/// Writes the whole database to `writer` as a pretty-printed JSON object and returns the
+	/// number of entries written.
+	///
+	/// Entries keep the order from [`list_ordered`][EasyDB::list_ordered], so exporting an
+	/// unchanged database twice produces the same bytes. The output can be read back with
+	/// [`import_json`][EasyDB::import_json].
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// # edb.put("hello", "world")?;
+	/// let mut backup = Vec::new();
+	/// let count = edb.export_json(&mut backup)?;
+	/// assert_eq!(count, 1);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn export_json<W>(&self, writer: W) -> EdbResult<usize>
+	where
+		W: Write,
+	{
+		let entries = self.list_ordered()?;
+		let mut serializer = serde_json::Serializer::pretty(writer);
+		serializer.collect_map(entries.iter().map(|(k, v)| (k, v)))?;
+		Ok(entries.len())
+	}
+	/// Returns all the data in this database as a single JSON object, for code that wants a
+	/// [`Json`] rather than a `HashMap`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let object = edb.as_json()?;
+	/// assert!(object.is_object());
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn as_json(&self) -> EdbResult<Json> {
+		Ok(Json::Object(self.list_json()?.into_iter().collect()))
+	}
+	/// Returns the sorted keys whose values satisfy `predicate`.
+	///
+	/// This fetches the entire database and checks every value locally, so it takes O(n) time
+	/// and transfers every value even if few match.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let active = edb.find_keys_with_value(|v| v["status"] == "active")?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn find_keys_with_value(
+		&self,
+		predicate: impl Fn(&Json) -> bool,
+	) -> EdbResult<Vec<String>> {
+		let mut keys: Vec<_> = self
+			.list_json()?
+			.into_iter()
+			.filter(|(_, value)| predicate(value))
+			.map(|(key, _)| key)
+			.collect();
+		keys.sort();
+		Ok(keys)
+	}
+	/// Returns how many keys satisfy `predicate`.
+	///
+	/// The whole database is still fetched, but values are skipped while parsing instead of being
+	/// kept in memory, so this is cheaper than counting the keys of [`list_json`][EasyDB::list_json].
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let sessions = edb.count_matching(|key| key.starts_with("session:"))?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn count_matching(&self, predicate: impl Fn(&str) -> bool) -> EdbResult<usize> {
+		let keys: HashMap<String, IgnoredAny> =
+			self.fetch(None, &RequestOptions::default())?.parse()?;
+		Ok(keys.keys().filter(|key| predicate(key)).count())
+	}
+	/// Checks whether the server can be reached and accepts the token, and how long it takes to
+	/// answer.
+	///
+	/// This sends one `GET` to the database URL, without retries, and only waits for the
+	/// response headers, so the body isn't downloaded. The exception is a handle with
+	/// [`max_concurrency`][EasyDBBuilder::max_concurrency] set, which reads every body in full,
+	/// so there each check downloads the whole database. It's meant for readiness probes: an
+	/// unreachable server is reported as [`HealthStatus::Unreachable`] instead of an error, and a
+	/// rejected token as [`HealthStatus::Unauthorized`].
+	///
+	/// # Errors
+	///
+	/// Only fails if the request can't be built, such as when an HTTP client with a custom
+	/// timeout can't be created.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let health = edb.health()?;
+	/// println!("{}", health);
+	/// assert!(health.is_healthy());
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn health(&self) -> EdbResult<Health> {
+		let opts = RequestOptions {
+			max_retries: Some(0),
+			..RequestOptions::default()
+		};
+		let start = Instant::now();
+		let result = self.request_with_opts(Method::GET, None, None, &opts);
+		let latency = start.elapsed();
+		let resp = match result {
+			Ok(resp) => resp,
+			Err(e) => match e.kind() {
+				EdbErrorKind::Request(_) => {
+					return Ok(Health {
+						status: HealthStatus::Unreachable,
+						http_status: None,
+						latency,
+						error: Some(e.to_string()),
+					})
+				}
+				_ => return Err(e),
+			},
+		};
+		let code = resp.status();
+		let status = if code.is_success() {
+			HealthStatus::Healthy
+		} else if code == StatusCode::UNAUTHORIZED || code == StatusCode::FORBIDDEN {
+			HealthStatus::Unauthorized
+		} else {
+			HealthStatus::Unhealthy
+		};
+		Ok(Health {
+			status,
+			http_status: Some(code.as_u16()),
+			latency,
+			error: None,
+		})
+	}
+	/// Measures how far the server's clock is from the local one, from the `Date` header of one
+	/// request.
+	///
+	/// Like [`health`][EasyDB::health], this sends one `GET` to the database URL and, unless
+	/// [`max_concurrency`][EasyDBBuilder::max_concurrency] is set, doesn't download the body.
+	/// The server's time is compared with the local time halfway through
+	/// the request. `Date` only has whole seconds, so the result is accurate to about a second
+	/// plus half the round trip, which is enough to spot a badly set clock but not for fine
+	/// synchronization.
+	///
+	/// # Errors
+	///
+	/// Will fail if the request fails, or if the response has no valid `Date` header. Servers
+	/// are expected to send one, but easydb.io doesn't promise to.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let offset = edb.server_time_offset()?;
+	/// if offset.abs().as_secs() > 30 {
+	///     eprintln!("Clock skew: {}", offset);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn server_time_offset(&self) -> EdbResult<ClockOffset> {
+		let before = SystemTime::now();
+		let resp = self.request_with_opts(Method::GET, None, None, &RequestOptions::default())?;
+		let after = SystemTime::now();
+		let date = resp
+			.headers()
+			.get(DATE)
+			.ok_or_else(|| EdbError::message("Response had no Date header"))?;
+		let server = date
+			.to_str()
+			.ok()
+			.and_then(|date| httpdate::parse_http_date(date).ok())
+			.ok_or_else(|| EdbError::message(format!("Invalid Date header: {:?}", date)))?;
+		let local = before + after.duration_since(before).unwrap_or_default() / 2;
+		Ok(match server.duration_since(local) {
+			Ok(ahead) => ClockOffset::Ahead(ahead),
+			Err(e) => ClockOffset::Behind(e.duration()),
+		})
+	}
+	/// Summarizes the contents of the database from a single [`list_json`][EasyDB::list_json].
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// edb.put("hello", "world")?;
+	/// let stats = edb.stats()?;
+	/// assert_eq!(stats.key_count, 1);
+	/// assert_eq!(stats.largest, Some(("hello".to_string(), 7)));
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn stats(&self) -> EdbResult<DbStats> {
+		Ok(stats::stats(&self.list_json()?))
+	}
+	/// Clears the database.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// edb.clear()?;
+	/// # std::thread::sleep(std::time::Duration::from_secs(1));
+	/// assert_eq!(edb.list()?.len(), 0);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn clear(&self) -> EdbResult<()> {
+		self.clear_count()?;
+		Ok(())
+	}
+	/// Clears the database and returns the number of keys that were successfully deleted.
+	///
+	/// With [`dry_run`][EasyDBBuilder::dry_run] enabled nothing is deleted, so this returns 0.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let deleted = edb.clear_count()?;
+	/// println!("Deleted {} keys", deleted);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn clear_count(&self) -> EdbResult<usize> {
+		let map = self.list_json()?;
+		self.clear_keys(map.keys().map(|k| &k[..]), None, &mut |_, _| {})
+	}
+	/// Like [`clear_count`][EasyDB::clear_count], but stops early once `cancel` is set.
+	///
+	/// `cancel` is checked before each key is deleted, so the delete in flight when it's set
+	/// still finishes. Set it from another thread, for example when the client that asked for
+	/// the clear disconnects.
+	///
+	/// # Errors
+	///
+	/// Fails with [`Cancelled`][crate::errors::EdbErrorKind::Cancelled], holding the number of
+	/// keys already deleted, if `cancel` was set before every key was deleted.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use std::sync::atomic::AtomicBool;
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let cancel = AtomicBool::new(false);
+	/// let deleted = edb.clear_cancellable(&cancel)?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn clear_cancellable(&self, cancel: &AtomicBool) -> EdbResult<usize> {
+		let map = self.list_json()?;
+		self.clear_keys(map.keys().map(|k| &k[..]), Some(cancel), &mut |_, _| {})
+	}
+	/// Like [`clear_count`][EasyDB::clear_count], but calls `progress(done, total)` after each
+	/// key has been handled.
+	///
+	/// `total` is the number of keys the database held when it was listed, and `done` counts up
+	/// to it, including keys whose delete returned a non-2xx status. Nothing is called for an
+	/// empty database.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// edb.clear_with_progress(|done, total| eprint!("\rDeleted {}/{}", done, total))?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn clear_with_progress(&self, mut progress: impl FnMut(usize, usize)) -> EdbResult<usize> {
+		let map = self.list_json()?;
+		self.clear_keys(map.keys().map(|k| &k[..]), None, &mut progress)
+	}
+	/// Like [`clear_count`][EasyDB::clear_count], but tries to delete every key even after some
+	/// fail, and reports which keys were and weren't deleted.
+	///
+	/// Keys are deleted in sorted order. A network error or non-2xx status for one key is
+	/// recorded in [`failed`][BatchReport::failed] and the rest are still attempted, so transient
+	/// errors leave behind only the keys they hit.
+	///
+	/// # Errors
+	///
+	/// Only fails if the database can't be listed.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let report = edb.clear_best_effort()?;
+	/// for (key, e) in &report.failed {
+	///     eprintln!("Couldn't delete {}: {}", key, e);
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn clear_best_effort(&self) -> EdbResult<BatchReport> {
+		let mut keys: Vec<_> = self.list_json()?.into_keys().collect();
+		keys.sort();
+		let mut report = BatchReport::default();
+		for key in keys {
+			let result = self.delete(&key);
+			report.record(key, result);
+		}
+		Ok(report)
+	}
+	/// Deletes every key whose value satisfies `predicate`, and returns the sorted keys that were
+	/// deleted.
+	///
+	/// Like [`find_keys_with_value`][EasyDB::find_keys_with_value], this fetches the entire
+	/// database and checks every value locally, then sends one request per match. Keys whose
+	/// delete returns a non-2xx status are left out of the result.
+	///
+	/// # Errors
+	///
+	/// Stops at the first failed request, leaving the deletes before it in place.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let deleted = edb.delete_where(|_key, value| value["archived"] == true)?;
+	/// println!("Purged {} archived records", deleted.len());
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn delete_where(&self, predicate: impl Fn(&str, &Json) -> bool) -> EdbResult<Vec<String>> {
+		let mut matches: Vec<_> = self
+			.list_json()?
+			.into_iter()
+			.filter(|(key, value)| predicate(key, value))
+			.map(|(key, _)| key)
+			.collect();
+		matches.sort();
+		let mut deleted = Vec::new();
+		for key in matches {
+			if self.delete(&key)?.is_success() {
+				deleted.push(key);
+			}
+		}
+		Ok(deleted)
+	}
+	fn clear_keys<'a, I>(
+		&self,
+		keys: I,
+		cancel: Option<&AtomicBool>,
+		progress: &mut dyn FnMut(usize, usize),
+	) -> EdbResult<usize>
+	where
+		I: ExactSizeIterator<Item = &'a str>,
+	{
+		let total = keys.len();
+		let mut count = 0;
+		for (i, key) in keys.enumerate() {
+			check_cancel(cancel, count)?;
+			// A dry run's simulated success didn't delete anything.
+			if self.delete(key)?.is_success() && !self.options.dry_run {
+				count += 1;
+			}
+			progress(i + 1, total);
+		}
+		Ok(count)
+	}
+	/// Compares the database with `desired` and returns the changes that
+	/// [`reset_to`][EasyDB::reset_to] would make, without making them.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use std::collections::HashMap;
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let report = edb.diff(&HashMap::new())?;
+	/// println!("Would delete {:?}", report.removed);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn diff(&self, desired: &HashMap<String, Json>) -> EdbResult<SyncReport> {
+		Ok(sync::diff(&self.list_json()?, desired))
+	}
+	/// Makes the database hold exactly `desired`, and returns the changes that were made.
+	///
+	/// Keys missing from `desired` are deleted, and keys that are new or have a different value
+	/// are written. Keys that already match are left alone, so calling this again with the same
+	/// `desired` makes no changes.
+	///
+	/// # Errors
+	///
+	/// Stops at the first failed request, leaving the changes before it in place.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use std::collections::HashMap;
+	/// # use serde_json::json;
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let mut desired = HashMap::new();
+	/// desired.insert("hello".to_string(), json!("world"));
+	/// let report = edb.reset_to(desired)?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn reset_to(&self, desired: HashMap<String, Json>) -> EdbResult<SyncReport> {
+		self.reset_to_inner(desired, None, &mut |_, _| {})
+	}
+	/// Replaces the whole contents of the database with `data`. The same as
+	/// [`reset_to`][EasyDB::reset_to], named for full refreshes.
+	///
+	/// Only the differences are sent, with the writes before the deletes, so readers never see
+	/// an empty database partway through. It still isn't atomic: until it returns, readers can
+	/// see a mix of old and new entries.
+	pub fn replace_all(&self, data: HashMap<String, Json>) -> EdbResult<SyncReport> {
+		self.reset_to(data)
+	}
+	/// Like [`reset_to`][EasyDB::reset_to], but stops early once `cancel` is set.
+	///
+	/// `cancel` is checked before each change.
+	///
+	/// # Errors
+	///
+	/// Fails with [`Cancelled`][crate::errors::EdbErrorKind::Cancelled], holding the number of
+	/// changes already made, if `cancel` was set before every change was made.
+	pub fn reset_to_cancellable(
+		&self,
+		desired: HashMap<String, Json>,
+		cancel: &AtomicBool,
+	) -> EdbResult<SyncReport> {
+		self.reset_to_inner(desired, Some(cancel), &mut |_, _| {})
+	}
+	/// Like [`reset_to`][EasyDB::reset_to], but calls `progress(done, total)` after each change.
+	///
+	/// `total` is the number of changes found by the initial [`diff`][EasyDB::diff]. Nothing is
+	/// called when there are no changes to make.
+	pub fn reset_to_with_progress(
+		&self,
+		desired: HashMap<String, Json>,
+		mut progress: impl FnMut(usize, usize),
+	) -> EdbResult<SyncReport> {
+		self.reset_to_inner(desired, None, &mut progress)
+	}
+	fn reset_to_inner(
+		&self,
+		desired: HashMap<String, Json>,
+		cancel: Option<&AtomicBool>,
+		progress: &mut dyn FnMut(usize, usize),
+	) -> EdbResult<SyncReport> {
+		let report = self.diff(&desired)?;
+		let total = report.added.len() + report.updated.len() + report.removed.len();
+		let mut done = 0;
+		for key in report.added.iter().chain(&report.updated) {
+			check_cancel(cancel, done)?;
+			self.put_json(key, &desired[key])?;
+			done += 1;
+			progress(done, total);
+		}
+		for key in &report.removed {
+			check_cancel(cancel, done)?;
+			self.delete(key)?;
+			done += 1;
+			progress(done, total);
+		}
+		Ok(report)
+	}
+	/// Reads a JSON object from `reader` and writes each of its members as a key and value.
+	///
+	/// `on_conflict` decides what happens to keys that already exist in the database. Keys are
+	/// written in sorted order, and a failed key doesn't stop the rest of the import.
+	/// If the object has the same key more than once, only its last value is written.
+	///
+	/// # Errors
+	///
+	/// Will fail if `reader` doesn't contain a JSON object, if the database can't be listed to
+	/// check for conflicts, or, with [`OnConflict::Error`], if any key already exists.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, OnConflict, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let backup = r#"{"hello": "world", "count": 3}"#;
+	/// let report = edb.import_json(backup.as_bytes(), OnConflict::Skip)?;
+	/// println!("Imported {}, skipped {}", report.succeeded.len(), report.skipped.len());
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn import_json<R>(&self, reader: R, on_conflict: OnConflict) -> EdbResult<BatchReport>
+	where
+		R: Read,
+	{
+		self.import_json_inner(reader, on_conflict, None, &mut |_, _| {})
+	}
+	/// Like [`import_json`][EasyDB::import_json], but stops early once `cancel` is set.
+	///
+	/// `cancel` is checked before each key is written.
+	///
+	/// # Errors
+	///
+	/// Fails with [`Cancelled`][crate::errors::EdbErrorKind::Cancelled], holding the number of
+	/// keys already written or skipped, if `cancel` was set before every key was handled.
+	pub fn import_json_cancellable<R>(
+		&self,
+		reader: R,
+		on_conflict: OnConflict,
+		cancel: &AtomicBool,
+	) -> EdbResult<BatchReport>
+	where
+		R: Read,
+	{
+		self.import_json_inner(reader, on_conflict, Some(cancel), &mut |_, _| {})
+	}
+	/// Like [`import_json`][EasyDB::import_json], but calls `progress(done, total)` after each
+	/// key has been written, skipped, or failed.
+	///
+	/// `total` is the number of members in the imported object.
+	pub fn import_json_with_progress<R>(
+		&self,
+		reader: R,
+		on_conflict: OnConflict,
+		mut progress: impl FnMut(usize, usize),
+	) -> EdbResult<BatchReport>
+	where
+		R: Read,
+	{
+		self.import_json_inner(reader, on_conflict, None, &mut progress)
+	}
+	fn import_json_inner<R>(
+		&self,
+		reader: R,
+		on_conflict: OnConflict,
+		cancel: Option<&AtomicBool>,
+		progress: &mut dyn FnMut(usize, usize),
+	) -> EdbResult<BatchReport>
+	where
+		R: Read,
+	{
+		let data: HashMap<String, Json> = serde_json::from_reader(reader)?;
+		let existing = match on_conflict {
+			OnConflict::Overwrite => HashMap::new(),
+			OnConflict::Skip | OnConflict::Error => self.list_json()?,
+		};
+		let mut entries: Vec<_> = data.into_iter().collect();
+		entries.sort_by(|a, b| a.0.cmp(&b.0));
+		if on_conflict == OnConflict::Error {
+			let conflicts: Vec<_> = entries
+				.iter()
+				.filter(|(key, _)| existing.contains_key(key))
+				.map(|(key, _)| key.clone())
+				.collect();
+			if !conflicts.is_empty() {
+				return Err(EdbErrorKind::ImportConflict(conflicts).into());
+			}
+		}
+		let mut report = BatchReport::default();
+		let total = entries.len();
+		for (i, (key, value)) in entries.into_iter().enumerate() {
+			check_cancel(cancel, i)?;
+			if existing.contains_key(&key) {
+				report.skipped.push(key);
+			} else {
+				let result = self.put_json(&key, value);
+				report.record(key, result);
+			}
+			progress(i + 1, total);
+		}
+		Ok(report)
+	}
+	/// Replaces every value in the database with `f(key, value)`, and returns which keys were
+	/// written.
+	///
+	/// This is a full read-modify-write: the whole database is listed, and then every value that
+	/// `f` changes is written back one request at a time, in sorted key order. Values that `f`
+	/// returns unchanged aren't written and are reported as skipped. Writes made by someone else
+	/// between the list and the write back are overwritten.
+	///
+	/// # Errors
+	///
+	/// Will fail if the database can't be listed. A failed write doesn't stop the rest.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use serde_json::json;
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// // Add a version field to every object.
+	/// let report = edb.map_values(|_key, mut value| {
+	///     if let Some(object) = value.as_object_mut() {
+	///         object.insert("version".to_string(), json!(2));
+	///     }
+	///     value
+	/// })?;
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn map_values<F>(&self, f: F) -> EdbResult<BatchReport>
+	where
+		F: Fn(&str, Json) -> Json,
+	{
+		let mut entries: Vec<_> = self.list_json()?.into_iter().collect();
+		entries.sort_by(|a, b| a.0.cmp(&b.0));
+		let mut report = BatchReport::default();
+		for (key, old) in entries {
+			let new = f(&key, old.clone());
+			if new == old {
+				report.skipped.push(key);
+			} else {
+				let result = self.put_json(&key, new);
+				report.record(key, result);
+			}
+		}
+		Ok(report)
+	}
+	/// Rewrites every value in the database as `transform(key, stored)`, for migrating values
+	/// written by older clients or under other settings.
+	///
+	/// Unlike [`map_values`][EasyDB::map_values], `transform` sees each value as it's stored,
+	/// before any codec or checksum is applied, and every result is written back
+	/// through this handle's settings even if it's unchanged. For example, with
+	/// [`checksums`][EasyDBBuilder::checksums] enabled and an identity `transform`, every value
+	/// that was written without a checksum gets one. A value that is already in the current
+	/// format has to be recognized by `transform` and unwrapped, or it's wrapped twice.
+	///
+	/// This costs one list and one write per key, in sorted key order, and isn't atomic: other
+	/// clients can see a mix of old and new formats until it finishes, and their writes in the
+	/// meantime are overwritten.
+	///
+	/// # Errors
+	///
+	/// Will fail if the database can't be listed. A failed write doesn't stop the rest.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let report = edb.compact(|_key, value| value)?;
+	/// assert!(report.is_success());
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn compact<F>(&self, transform: F) -> EdbResult<BatchReport>
+	where
+		F: Fn(String, Json) -> Json,
+	{
+		let mut entries = self.list_stored()?;
+		entries.sort_by(|a, b| a.0.cmp(&b.0));
+		let mut report = BatchReport::default();
+		for (key, stored) in entries {
+			let result = self.put_json(&key, transform(key.clone(), stored));
+			report.record(key, result);
+		}
+		Ok(report)
+	}
+	/// Writes every key and value in `entries`, with up to `concurrency` requests in flight at
+	/// once.
+	///
+	/// This crate has no async client, only `reqwest`'s blocking one, which sends one request at
+	/// a time per thread. So instead of a stream of futures, the writes are spread over
+	/// `concurrency` threads that share this `EasyDB`. A `concurrency` of 0 is treated as 1.
+	///
+	/// A failed key doesn't stop the rest of the batch, so the result is a [`BatchReport`] of
+	/// every key rather than the first error. Use [`BatchReport::into_result`] for an
+	/// [`EdbResult`] that fails if any key did.
+	///
+	/// Each key is written once. If `entries` has the same key more than once, the last value
+	/// wins and the earlier ones are dropped before anything is sent. Keys in the report are in
+	/// the order of their last occurrence in `entries`.
+	///
+	/// Every thread retries on its own, so a large `concurrency` can make the server start
+	/// returning 429 errors. Setting [`max_retries`][EasyDBBuilder::max_retries] lets those writes
+	/// back off and try again.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use serde_json::json;
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let entries = (0..100).map(|i| (format!("key{}", i), json!(i)));
+	/// let report = edb.put_many(entries, 8);
+	/// assert!(report.is_success());
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put_many<I, K, V>(&self, entries: I, concurrency: usize) -> BatchReport
+	where
+		I: IntoIterator<Item = (K, V)>,
+		K: Into<String>,
+		V: Serialize + Sync,
+	{
+		let mut entries: Vec<(String, V)> =
+			entries.into_iter().map(|(k, v)| (k.into(), v)).collect();
+		let mut seen = HashSet::new();
+		entries.reverse();
+		entries.retain(|(key, _)| seen.insert(key.clone()));
+		entries.reverse();
+		let next = AtomicUsize::new(0);
+		let results = Mutex::new(Vec::with_capacity(entries.len()));
+		thread::scope(|scope| {
+			for _ in 0..concurrency.clamp(1, entries.len().max(1)) {
+				scope.spawn(|| loop {
+					let i = next.fetch_add(1, Ordering::Relaxed);
+					let (key, value) = match entries.get(i) {
+						Some(entry) => entry,
+						None => break,
+					};
+					let result = self.put_json(key, value);
+					results.lock().unwrap().push((i, result));
+				});
+			}
+		});
+		let mut results = results.into_inner().unwrap();
+		results.sort_by_key(|(i, _)| *i);
+		let mut report = BatchReport::default();
+		for (i, result) in results {
+			report.record(entries[i].0.clone(), result);
+		}
+		report
+	}
+	/// Writes each top-level member of the JSON object `object` as a key and value, one request
+	/// at a time, and returns which keys were written.
+	///
+	/// A failed key doesn't stop the rest. For many members, [`put_many`][EasyDB::put_many]
+	/// can write them concurrently.
+	///
+	/// # Errors
+	///
+	/// Will fail without writing anything if `object` isn't a JSON object.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use serde_json::json;
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let config = json!({"theme": "dark", "retries": 3});
+	/// let report = edb.put_object_entries(config)?;
+	/// assert_eq!(report.succeeded.len(), 2);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn put_object_entries(&self, object: Json) -> EdbResult<BatchReport> {
+		let object = match object {
+			Json::Object(object) => object,
+			_ => return Err(EdbError::message("Value was not an object")),
+		};
+		let mut report = BatchReport::default();
+		for (key, value) in object {
+			let result = self.put_json(&key, value);
+			report.record(key, result);
+		}
+		Ok(report)
+	}
+	/// Like [`get`][EasyDB::get], but also returns how long the call took.
+	///
+	/// The `*_timed` methods measure wall-clock time from before the first request is sent until
+	/// the response has been parsed, including any retries.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let (value, took) = edb.get_timed("somekey")?;
+	/// println!("Got {} in {:?}", value, took);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn get_timed(&self, key: impl AsRef<str>) -> EdbResult<(String, Duration)> {
+		timed(|| self.get(key))
+	}
+	/// Like [`get_json`][EasyDB::get_json], but also returns how long the call took.
+	pub fn get_json_timed(&self, key: impl AsRef<str>) -> EdbResult<(Json, Duration)> {
+		timed(|| self.get_json(key))
+	}
+	/// Like [`put`][EasyDB::put], but also returns how long the call took.
+	pub fn put_timed(
+		&self,
+		key: impl AsRef<str>,
+		value: &str,
+	) -> EdbResult<(WriteOutcome, Duration)> {
+		timed(|| self.put(key, value))
+	}
+	/// Like [`put_json`][EasyDB::put_json], but also returns how long the call took.
+	pub fn put_json_timed(
+		&self,
+		key: impl AsRef<str>,
+		value: impl Serialize,
+	) -> EdbResult<(WriteOutcome, Duration)> {
+		timed(|| self.put_json(key, value))
+	}
+	/// Like [`delete`][EasyDB::delete], but also returns how long the call took.
+	pub fn delete_timed(&self, key: impl AsRef<str>) -> EdbResult<(WriteOutcome, Duration)> {
+		timed(|| self.delete(key))
+	}
+	/// Like [`list`][EasyDB::list], but also returns how long the call took.
+	pub fn list_timed(&self) -> EdbResult<(HashMap<String, String>, Duration)> {
+		timed(|| self.list())
+	}
+	/// Like [`list_json`][EasyDB::list_json], but also returns how long the call took.
+	pub fn list_json_timed(&self) -> EdbResult<(HashMap<String, Json>, Duration)> {
+		timed(|| self.list_json())
+	}
+	/// Sends a `GET` for `key` and returns the response without reading its body, for streaming
+	/// a large value or inspecting headers. [`get_writer`][EasyDB::get_writer] is built on the
+	/// same request.
+	///
+	/// Retries, [`rate_limit`][EasyDBBuilder::rate_limit], and
+	/// [`fallback_tokens`][EasyDBBuilder::fallback_tokens] apply as usual, but nothing else
+	/// does: the status isn't checked, and the body isn't decoded or verified. A missing key
+	/// usually comes back as `200 OK` with the body `""`.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, errors::EdbError};
+	/// # use std::io::Read;
+	/// # let edb = EasyDB::in_memory()?;
+	/// let mut resp = edb.get_response("somekey")?;
+	/// if resp.status().is_success() {
+	///     let mut body = String::new();
+	///     resp.read_to_string(&mut body)?;
+	/// }
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn get_response(&self, key: impl AsRef<str>) -> EdbResult<Response> {
+		self.request_with_opts(
+			Method::GET,
+			Some(key.as_ref()),
+			None,
+			&RequestOptions::default(),
+		)
+	}
+	/// An alternative to `get()` that works with a writer. Fetches data associated with `key` and
+	/// writes into `value`, returning the status code.
+	///
+	/// The response should have the value that was originally set in JSON form. If the key was
+	/// never set, was deleted, or has no data, the response will be an empty string: `""`.
+	pub fn get_writer<W>(&self, key: impl AsRef<str>, value: &mut W) -> EdbResult<u16>
+	where
+		W: Write,
+	{
+		self.get_writer_with_opts(key.as_ref(), value, &RequestOptions::default())
+	}
+	fn get_writer_with_opts<W>(
+		&self,
+		key: &str,
+		value: &mut W,
+		opts: &RequestOptions,
+	) -> EdbResult<u16>
+	where
+		W: Write,
 	{
-		let mut resp = self
-			.client
-			.get(self.create_key_url(key)?)
-			.header("token", &self.token)
-			.send()?;
+		let mut resp = self.request_with_opts(Method::GET, Some(key), None, opts)?;
 		resp.copy_to(value)?;
 		Ok(resp.status().as_u16())
 	}
@@ -285,14 +2611,299 @@ impl EasyDB {
 	where
 		W: Write,
 	{
-		let mut resp = self
-			.client
-			.get(self.url.parse::<Url>().unwrap().join(&self.uuid).unwrap())
-			.header("token", &self.token)
-			.send()?;
+		let mut resp = self.raw_request(Method::GET, None, None)?;
 		resp.copy_to(list)?;
 		Ok(resp.status().as_u16())
 	}
+	/// Parses `body` with the configured [`JsonCodec`][crate::JsonCodec], or with `serde_json` if
+	/// there isn't one.
+	fn decode_value(&self, body: &Body) -> EdbResult<Json> {
+		match &self.options.codec {
+			Some(codec) => body.decode(|bytes| codec.decode(bytes)),
+			None => body.parse(),
+		}
+	}
+	/// Like [`decode_value`][EasyDB::decode_value], for the body of the whole database.
+	fn decode_object(&self, body: &Body) -> EdbResult<Map<String, Json>> {
+		match self.decode_value(body)? {
+			Json::Object(map) => Ok(map),
+			other => Err(EdbError::message(format!(
+				"Expected the database to be a JSON object, got {}",
+				other
+			))),
+		}
+	}
+	/// Sends a GET request for `key`, or for the whole database, and reads the whole body.
+	fn fetch(&self, key: Option<&str>, opts: &RequestOptions) -> EdbResult<Body> {
+		let mut resp = self.request_with_opts(Method::GET, key, None, opts)?;
+		let html = resp
+			.headers()
+			.get(CONTENT_TYPE)
+			.and_then(|ct| ct.to_str().ok())
+			.is_some_and(|ct| ct.contains("html"));
+		let mut bytes = Vec::new();
+		resp.copy_to(&mut bytes)?;
+		Ok(Body {
+			status: resp.status(),
+			html,
+			bytes,
+		})
+	}
+	/// Sends a request with the token attached and returns the response as-is.
+	///
+	/// The request goes to the URL for `key`, or to the database URL (the one `list` uses) when
+	/// `key` is `None`. A `body` is sent as JSON. This is an escape hatch for anything the other
+	/// methods don't cover: the caller is responsible for checking the status and reading the
+	/// body.
+	///
+	/// # Example
+	///
+	/// ```
+	/// # use crate::easydb::{EasyDB, Method, errors::EdbError};
+	/// # let edb = EasyDB::in_memory()?;
+	/// let mut resp = edb.raw_request(Method::GET, Some("somekey"), None)?;
+	/// println!("{}: {}", resp.status(), resp.text()?);
+	/// # Ok::<(), EdbError>(())
+	/// ```
+	///
+	pub fn raw_request(
+		&self,
+		method: Method,
+		key: Option<&str>,
+		body: Option<String>,
+	) -> EdbResult<Response> {
+		self.request_with_opts(method, key, body, &RequestOptions::default())
+	}
+	fn request_with_opts(
+		&self,
+		method: Method,
+		key: Option<&str>,
+		body: Option<String>,
+		opts: &RequestOptions,
+	) -> EdbResult<Response> {
+		let payload = body.map(|body| Payload {
+			bytes: body.into_bytes(),
+			content_type: &self.options.content_type,
+		});
+		self.request_payload(method, key, payload, opts)
+	}
+	fn request_payload(
+		&self,
+		method: Method,
+		key: Option<&str>,
+		body: Option<Payload>,
+		opts: &RequestOptions,
+	) -> EdbResult<Response> {
+		let writes = method != Method::GET && method != Method::HEAD;
+		if self.options.read_only && writes {
+			return Err(EdbErrorKind::ReadOnly.into());
+		}
+		let url = self.request_url(key, &opts.query)?;
+		if self.options.dry_run && writes {
+			edb_log!(info, "Dry run: skipped {} {}", method, url);
+			return Ok(http::Response::new(Vec::new()).into());
+		}
+		self.send(method, url, body, opts)
+	}
+	/// Builds the URL for `key`, or for the whole database, with `query` appended.
+	fn request_url(&self, key: Option<&str>, query: &[(String, String)]) -> EdbResult<Url> {
+		let mut url = match key {
+			Some(key) => self.create_key_url(key)?,
+			None => self.database_url_parsed()?,
+		};
+		if !query.is_empty() {
+			url.query_pairs_mut().extend_pairs(query);
+		}
+		Ok(url)
+	}
+	/// Returns a client built from this handle's settings with another timeout, reusing one
+	/// built earlier for the same timeout.
+	fn timeout_client(&self, timeout: Duration) -> EdbResult<Client> {
+		if self.options.client.custom {
+			return Err(EdbError::message(
+				"A per-call timeout needs a client built by EasyDB, not one passed to from_parts",
+			));
+		}
+		let mut clients = self.timeout_clients.lock().unwrap();
+		if let Some(client) = clients.get(&timeout) {
+			return Ok(client.clone());
+		}
+		let client = self.options.client.with_timeout(timeout).build()?;
+		clients.insert(timeout, client.clone());
+		Ok(client)
+	}
+	/// Sends a request, retrying network errors, 5xx, and 429 responses up to the configured
+	/// number of times. A response that is still failing after the last retry is returned as-is.
+	fn send(
+		&self,
+		method: Method,
+		url: Url,
+		body: Option<Payload>,
+		opts: &RequestOptions,
+	) -> EdbResult<Response> {
+		let client = match opts.timeout {
+			Some(timeout) => self.timeout_client(timeout)?,
+			None => self.client.clone(),
+		};
+		// Every attempt and fallback token reuses the same ID.
+		let generated;
+		let opts = match (&self.options.request_id_header, &opts.request_id) {
+			(Some(_), None) => {
+				generated = RequestOptions {
+					request_id: Some(new_request_id()),
+					..opts.clone()
+				};
+				&generated
+			}
+			_ => opts,
+		};
+		let id = log_prefix(
+			self.options
+				.request_id_header
+				.as_ref()
+				.and(opts.request_id.as_ref()),
+		);
+		let tokens: Vec<&str> = std::iter::once(&self.token[..])
+			.chain(self.options.fallback_tokens.iter().map(|t| &t[..]))
+			.collect();
+		for (i, token) in tokens.iter().enumerate() {
+			let resp = self.send_with_token(&client, token, &method, &url, &body, opts)?;
+			if resp.status() != StatusCode::UNAUTHORIZED || i + 1 == tokens.len() {
+				return Ok(resp);
+			}
+			edb_log!(
+				info,
+				"{}{} {} was unauthorized with token {} of {}, trying the next one",
+				id,
+				method,
+				url,
+				i + 1,
+				tokens.len()
+			);
+		}
+		unreachable!("there is always at least one token")
+	}
+	/// Sends a request with `token`, retrying as configured.
+	fn send_with_token(
+		&self,
+		client: &Client,
+		token: &str,
+		method: &Method,
+		url: &Url,
+		body: &Option<Payload>,
+		opts: &RequestOptions,
+	) -> EdbResult<Response> {
+		let max_retries = opts.max_retries.unwrap_or(self.options.max_retries);
+		let request_id = self
+			.options
+			.request_id_header
+			.as_ref()
+			.zip(opts.request_id.as_ref());
+		let id = log_prefix(request_id.map(|(_, id)| id));
+		let mut attempt = 0;
+		let start = Instant::now();
+		loop {
+			let mut request = client
+				.request(method.clone(), url.clone())
+				.header("token", token);
+			if let Some((name, id)) = request_id {
+				request = request.header(&name[..], &id[..]);
+			}
+			if let Some(body) = body {
+				request = request
+					.header(CONTENT_TYPE, body.content_type)
+					.header(CONTENT_LENGTH, body.bytes.len())
+					.body(body.bytes.clone());
+			}
+			if let Some(limiter) = &self.options.rate_limit {
+				limiter.acquire();
+			}
+			let result = match &self.options.max_concurrency {
+				Some(semaphore) => {
+					let _permit = semaphore.acquire();
+					request.send().and_then(buffered)
+				}
+				None => request.send(),
+			};
+			let context = RetryContext {
+				attempt: attempt + 1,
+				result: &result,
+				elapsed: start.elapsed(),
+			};
+			let retry = match &self.options.retry_on {
+				Some(predicate) => predicate.0(&context),
+				None => context.is_retryable_by_default(),
+			};
+			let reason = match &result {
+				Ok(resp) if !retry => {
+					edb_log!(
+						debug,
+						"{}{} {} -> {} after {} attempt(s)",
+						id,
+						method,
+						url,
+						resp.status(),
+						attempt + 1
+					);
+					return Ok(result?);
+				}
+				Ok(resp) => format!("status {}", resp.status()),
+				Err(e) => format!("network error: {}", e),
+			};
+			if !retry || attempt >= max_retries {
+				edb_log!(
+					warn,
+					"{}{} {} failed after {} attempt(s): {}",
+					id,
+					method,
+					url,
+					attempt + 1,
+					reason
+				);
+				return Ok(result?);
+			}
+			let delay = self.options.retry_delay(attempt);
+			edb_log!(
+				info,
+				"{}{} {} attempt {} failed ({}), retrying in {:?}",
+				id,
+				method,
+				url,
+				attempt + 1,
+				reason,
+				delay
+			);
+			thread::sleep(delay);
+			attempt += 1;
+		}
+	}
+}
+
+impl TryFrom<&Path> for EasyDB {
+	type Error = EdbError;
+	/// Create an `EasyDB` from a TOML file. See [`from_toml_path`][EasyDB::from_toml_path].
+	fn try_from(path: &Path) -> Result<Self, Self::Error> {
+		Self::from_toml_path(path)
+	}
+}
+
+impl TryFrom<EasyDBConfig> for EasyDB {
+	type Error = EdbError;
+	/// Create an `EasyDB` from a config, like [`from_uuid_token`][EasyDB::from_uuid_token].
+	fn try_from(config: EasyDBConfig) -> Result<Self, Self::Error> {
+		Self::from_uuid_token(config.uuid, config.token, Some(config.url))
+	}
+}
+
+impl TryFrom<&str> for EasyDB {
+	type Error = EdbError;
+	/// Create an `EasyDB` from a `&str` in the TOML format, like [`parse`][str::parse].
+	///
+	/// The `&str` is the contents of the file, not a path. Use [`Path::new`] to convert from a
+	/// path instead.
+	fn try_from(s: &str) -> Result<Self, Self::Error> {
+		s.parse()
+	}
 }
 
 impl FromStr for EasyDB {
@@ -1,3 +1,6 @@
+// `error_chain!` checks a cfg that only its own build script knows about.
+#![allow(unexpected_cfgs)]
+
 use error_chain::error_chain;
 
 error_chain! {
@@ -12,4 +15,125 @@ error_chain! {
 		FromUtf8(std::string::FromUtf8Error);
 		FromJson(serde_json::Error);
 	}
-}
\ No newline at end of file
+	errors {
+		/// The database returned something other than the value this handle last wrote, which
+		/// usually means the write hasn't propagated yet. Only returned when stale read
+		/// detection is enabled.
+		StaleRead(key: String, value: crate::Json) {
+			description("stale read")
+			display("Stale read of key {}: got {} instead of the last written value", key, value)
+		}
+		/// The server returned an HTML page or another body that isn't JSON, such as a gateway
+		/// error or a maintenance page. Holds the status code and the start of the body.
+		NonJsonResponse(status: u16, snippet: String) {
+			description("non-JSON response")
+			display("Server returned a non-JSON response with status {}: {}", status, snippet)
+		}
+		/// The URL for a key would be longer than the configured limit.
+		KeyTooLong(len: usize, max: usize) {
+			description("key too long")
+			display("Key is too long: its URL would be {} bytes, but the limit is {}", len, max)
+		}
+		/// A long operation was stopped because its cancellation flag was set. Holds how many
+		/// items were completed first.
+		Cancelled(done: usize) {
+			description("cancelled")
+			display("Cancelled after {} items", done)
+		}
+		/// A write was attempted through a handle built with
+		/// [`read_only`][crate::EasyDBBuilder::read_only].
+		ReadOnly {
+			description("read-only handle")
+			display("This EasyDB is read-only")
+		}
+		/// The server returned an unexpected status. Holds the status code and a description,
+		/// such as the status's reason phrase.
+		BadStatus(status: u16, message: String) {
+			description("bad status")
+			display("Server returned status {}: {}", status, message)
+		}
+		/// The URL doesn't end with a slash, so keys would replace its last segment instead of
+		/// being appended. Only returned with [`TrailingSlash::Strict`][crate::TrailingSlash].
+		MissingTrailingSlash(url: String) {
+			description("missing trailing slash")
+			display("URL {} must end with a slash", url)
+		}
+		/// A [`Key`][crate::Key] couldn't be created. Holds the key and why it was rejected.
+		InvalidKey(key: String, reason: String) {
+			description("invalid key")
+			display("Invalid key {:?}: {}", key, reason)
+		}
+		/// A value read with [`checksums`][crate::EasyDBBuilder::checksums] enabled didn't match
+		/// its checksum, or wasn't stored with one. Holds the key.
+		ChecksumMismatch(key: String) {
+			description("checksum mismatch")
+			display("Value of key {} failed checksum verification", key)
+		}
+		/// A read found no value for a key, with
+		/// [`MissingKeyBehavior::Error`][crate::MissingKeyBehavior::Error]. Holds the key.
+		MissingKey(key: String) {
+			description("missing key")
+			display("Key {} is not set", key)
+		}
+		/// An import with [`OnConflict::Error`][crate::OnConflict::Error] found keys that already
+		/// exist.
+		ImportConflict(keys: Vec<String>) {
+			description("import conflict")
+			display("Keys already exist: {}", keys.join(", "))
+		}
+		/// Some keys of a batch couldn't be written. Holds each failed key with the reason, as
+		/// returned by [`BatchReport::into_result`][crate::BatchReport::into_result].
+		Batch(failures: Vec<(String, EdbError)>) {
+			description("batch failed")
+			display("{} keys failed: {}", failures.len(), failures
+				.iter()
+				.map(|(key, e)| format!("{} ({})", key, e))
+				.collect::<Vec<_>>()
+				.join(", "))
+		}
+	}
+}
+
+impl EdbError {
+	/// Creates an error with only a message, shown as-is when the error is displayed.
+	///
+	/// ```
+	/// # use easydb::errors::{EdbError, EdbErrorKind};
+	/// let e = EdbError::message("Value was not a string");
+	/// assert!(matches!(e.kind(), EdbErrorKind::Msg(_)));
+	/// assert_eq!(e.to_string(), "Value was not a string");
+	/// ```
+	pub fn message(message: impl Into<String>) -> Self {
+		EdbErrorKind::Msg(message.into()).into()
+	}
+	/// Creates a [`BadStatus`][EdbErrorKind::BadStatus] error for an unexpected status code.
+	///
+	/// ```
+	/// # use easydb::errors::{EdbError, EdbErrorKind};
+	/// let e = EdbError::bad_status(503, "Service Unavailable".to_string());
+	/// assert!(matches!(e.kind(), EdbErrorKind::BadStatus(503, _)));
+	/// ```
+	pub fn bad_status(status: u16, message: String) -> Self {
+		EdbErrorKind::BadStatus(status, message).into()
+	}
+	/// Returns the line and column where JSON parsing failed, as reported by `serde_json`. Both
+	/// count from 1.
+	///
+	/// Returns `None` unless this is a [`FromJson`][EdbErrorKind::FromJson] error that points at a
+	/// position in its input. Errors from converting values, rather than parsing text, have no
+	/// position.
+	///
+	/// ```
+	/// # use easydb::errors::EdbError;
+	/// let e: EdbError = serde_json::from_str::<serde_json::Value>("{\n  \"a\": @}")
+	///     .unwrap_err()
+	///     .into();
+	/// assert_eq!(e.json_location(), Some((2, 8)));
+	/// ```
+	pub fn json_location(&self) -> Option<(usize, usize)> {
+		match self.kind() {
+			EdbErrorKind::FromJson(e) if e.line() > 0 => Some((e.line(), e.column())),
+			_ => None,
+		}
+	}
+}
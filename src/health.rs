@@ -0,0 +1,56 @@
+use std::{fmt, time::Duration};
+
+/// The result of [`health`][crate::EasyDB::health].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Health {
+	/// What the check found.
+	pub status: HealthStatus,
+	/// The status code of the response, or `None` if there wasn't one.
+	pub http_status: Option<u16>,
+	/// How long it took to get the response headers, or to fail.
+	pub latency: Duration,
+	/// The network error, for [`HealthStatus::Unreachable`].
+	pub error: Option<String>,
+}
+
+impl Health {
+	/// Returns `true` if the server answered and accepted the token.
+	pub fn is_healthy(&self) -> bool {
+		self.status == HealthStatus::Healthy
+	}
+}
+
+impl fmt::Display for Health {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let ms = self.latency.as_millis();
+		let code = self.http_status.unwrap_or(0);
+		match self.status {
+			HealthStatus::Healthy => write!(f, "Healthy: status {} in {} ms", code, ms),
+			HealthStatus::Unauthorized => write!(
+				f,
+				"Reachable, but the token was rejected: status {} in {} ms",
+				code, ms
+			),
+			HealthStatus::Unhealthy => write!(f, "Unhealthy: status {} in {} ms", code, ms),
+			HealthStatus::Unreachable => write!(
+				f,
+				"Unreachable after {} ms: {}",
+				ms,
+				self.error.as_deref().unwrap_or("unknown error")
+			),
+		}
+	}
+}
+
+/// What [`health`][crate::EasyDB::health] found. Part of [`Health`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HealthStatus {
+	/// The server answered with a 2xx status.
+	Healthy,
+	/// The server answered, but rejected the token with 401 or 403.
+	Unauthorized,
+	/// The server answered with some other status, such as a 5xx or a 404 for an unknown UUID.
+	Unhealthy,
+	/// No response arrived, because of a DNS, connection, TLS, or timeout error.
+	Unreachable,
+}
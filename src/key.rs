@@ -0,0 +1,136 @@
+use crate::errors::{EdbErrorKind, EdbResult};
+use std::{convert::TryFrom, fmt, ops::Deref};
+
+/// A database key that has been checked to reach the server unchanged.
+///
+/// Every method that takes a key accepts a `Key` as well as a plain `&str` or `String`, so using
+/// `Key` is optional. It's for programs that want bad keys caught where they're created, such as
+/// when reading user input, instead of as a confusing failure in the middle of a request.
+///
+/// A key is valid if it:
+///
+/// - isn't empty, `.`, or `..`;
+/// - is at most [`Key::MAX_LEN`] bytes long;
+/// - doesn't contain `/`, `?`, `#`, or `\`, which would change which URL is requested;
+/// - doesn't start with something that looks like a URL scheme followed by `:`, such as
+///   `user:1`, which would be requested as a URL of its own;
+/// - doesn't contain control characters, or start or end with whitespace, which URL parsing
+///   would drop or mangle.
+///
+/// [`max_key_url_len`][crate::EasyDBBuilder::max_key_url_len] is still checked when the key is
+/// used, since the full URL depends on the handle.
+///
+/// # Example
+///
+/// ```
+/// # use easydb::Key;
+/// let key = Key::new("user-1")?;
+/// assert_eq!(key.as_str(), "user-1");
+/// assert!(Key::new("a/b").is_err());
+/// assert!(Key::new("user:1").is_err());
+/// # Ok::<(), easydb::errors::EdbError>(())
+/// ```
+///
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Key(String);
+
+impl Key {
+	/// The longest key allowed, in bytes.
+	pub const MAX_LEN: usize = 1000;
+
+	/// Checks `key` and wraps it.
+	///
+	/// # Errors
+	///
+	/// Fails with [`InvalidKey`][EdbErrorKind::InvalidKey] if `key` breaks one of the rules
+	/// above.
+	pub fn new(key: impl Into<String>) -> EdbResult<Self> {
+		let key = key.into();
+		if let Some(reason) = problem(&key) {
+			return Err(EdbErrorKind::InvalidKey(key, reason.to_string()).into());
+		}
+		Ok(Key(key))
+	}
+	/// Returns the key as a `&str`.
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+	/// Returns the key as a `String`.
+	pub fn into_string(self) -> String {
+		self.0
+	}
+}
+
+/// Returns why `key` isn't valid, or `None` if it is.
+fn problem(key: &str) -> Option<&'static str> {
+	if key.is_empty() {
+		Some("it is empty")
+	} else if key == "." || key == ".." {
+		Some("it is a relative path segment")
+	} else if key.len() > Key::MAX_LEN {
+		Some("it is too long")
+	} else if key.contains(['/', '?', '#', '\\']) {
+		Some("it contains '/', '?', '#', or '\\'")
+	} else if looks_like_scheme(key) {
+		Some("it starts with a URL scheme")
+	} else if key.contains(char::is_control) {
+		Some("it contains a control character")
+	} else if key.starts_with(char::is_whitespace) || key.ends_with(char::is_whitespace) {
+		Some("it starts or ends with whitespace")
+	} else {
+		None
+	}
+}
+
+/// Returns `true` if `key` would be parsed as an absolute URL, like `mailto:x`.
+fn looks_like_scheme(key: &str) -> bool {
+	match key.find(':') {
+		Some(end) => {
+			let scheme = &key[..end];
+			scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+				&& scheme
+					.chars()
+					.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+		}
+		None => false,
+	}
+}
+
+impl AsRef<str> for Key {
+	fn as_ref(&self) -> &str {
+		&self.0
+	}
+}
+
+impl Deref for Key {
+	type Target = str;
+	fn deref(&self) -> &str {
+		&self.0
+	}
+}
+
+impl fmt::Display for Key {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl TryFrom<&str> for Key {
+	type Error = crate::errors::EdbError;
+	fn try_from(key: &str) -> EdbResult<Self> {
+		Key::new(key)
+	}
+}
+
+impl TryFrom<String> for Key {
+	type Error = crate::errors::EdbError;
+	fn try_from(key: String) -> EdbResult<Self> {
+		Key::new(key)
+	}
+}
+
+impl From<Key> for String {
+	fn from(key: Key) -> String {
+		key.0
+	}
+}
@@ -4,7 +4,7 @@
 //!
 //! # Quick start
 //!
-//! ```
+//! ```no_run
 //! # use std::collections::HashMap;
 //! # use crate::easydb::errors::EdbError;
 //! use easydb::EasyDB;
@@ -17,8 +17,8 @@
 //! # std::thread::sleep(std::time::Duration::from_secs(1));
 //!
 //! // Store some data
-//!	edb.put("hello", "world")?;
-//!	edb.put("goodbye", "earth")?;
+//! edb.put("hello", "world")?;
+//! edb.put("goodbye", "earth")?;
 //! #
 //! # std::thread::sleep(std::time::Duration::from_secs(1));
 //!
@@ -32,12 +32,12 @@
 //! assert_eq!(&edb.get("goodbye")?, "dirt");
 //!
 //! // Get a HashMap of all database entries
-//!	let resp: HashMap<String, String> = edb.list()?;
-//!	assert_eq!(&resp["hello"], "world");
-//!	assert_eq!(&resp["goodbye"], "dirt");
+//! let resp: HashMap<String, String> = edb.list()?;
+//! assert_eq!(&resp["hello"], "world");
+//! assert_eq!(&resp["goodbye"], "dirt");
 //!
 //! // Delete items
-//!	edb.delete("hello")?;
+//! edb.delete("hello")?;
 //! # std::thread::sleep(std::time::Duration::from_secs(1));
 //! let deleted_item: String = edb.get("hello")?;
 //! assert_eq!(&deleted_item, "");
@@ -59,6 +59,10 @@
 //!
 //! The `URL` field is optional and will default to `https://app.easydb.io/database/`.
 //!
+//! To keep the file out of your project, put it in your platform's config directory (e.g.
+//! `~/.config/easydb/easydb.toml`) and use [`EasyDB::from_config_dir()`][EasyDB::from_config_dir]
+//! instead.
+//!
 //! If your toml is not at the default location, you can just
 //! [`parse`](./struct.EasyDB.html#method.from_str) it from a string in toml format. For example:
 //!
@@ -75,6 +79,17 @@
 //! let edb = EasyDB::from_uuid_token("aaaa...".to_string(), "ffff...".to_string(), None);
 //! ```
 //!
+//! To customize the HTTP client, for example to use HTTP/2, use [`EasyDB::builder()`][EasyDB::builder]:
+//!
+//! ```
+//! # use easydb::EasyDB;
+//! let edb = EasyDB::builder()
+//!     .uuid("aaaa...".to_string())
+//!     .token("ffff...".to_string())
+//!     .http2_prior_knowledge(true)
+//!     .build();
+//! ```
+//!
 //! ## Using EasyDB
 //!
 //! The four methods [**`get`**][EasyDB::get], [**`put`**][EasyDB::put],
@@ -83,12 +98,13 @@
 //! `key` and a `value`. `list` takes no arguments. Example usage can be seen in the
 //! [quick start](#quick-start) section at the top of this page.
 //!
-//! The above methods deal with [`String`](https://doc.rust-lang.org/std/string/struct.String.html) 
-//! values and will fail if any value is not a JSON string. If you would like to use JSON, there are 
-//! [**`get_json`**][EasyDB::get_json], [**`put_json`**][EasyDB::put_json], and 
-//! [**`list_json`**][EasyDB::list_json] ([**`delete`**][EasyDB::delete] is the same). These deal 
-//! with `value`s that are of the `Json` type, which is a re-export of the 
+//! The above methods deal with [`String`](https://doc.rust-lang.org/std/string/struct.String.html)
+//! values and will fail if any value is not a JSON string. If you would like to use JSON, there are
+//! [**`get_json`**][EasyDB::get_json], [**`put_json`**][EasyDB::put_json], and
+//! [**`list_json`**][EasyDB::list_json] ([**`delete`**][EasyDB::delete] is the same). These deal
+//! with `value`s that are of the `Json` type, which is a re-export of the
 //! [`Value`](https://docs.serde.rs/serde_json/enum.Value.html) type from `serde_json`.
+//! [`put_json`][EasyDB::put_json] also accepts any other type that implements `Serialize`.
 //!
 //! In addition, there is the [**`clear`**][EasyDB::clear] method for easily clearing the entire
 //! database, which, for example, is useful when initializing the database. This just calls
@@ -103,21 +119,100 @@
 //! Due to the unknown nature of the database, there may be unexpected results when reading data
 //! just after writing data. Expect that read values will be either up-to-date or old values.
 //!
+//! Requests that fail with a network error or a 5xx or 429 status can be retried automatically by
+//! setting [`max_retries`][EasyDBBuilder::max_retries] on the builder.
+//!
+//! # Features
+//!
+//! * `logging`: Logs retries and the outcome of each request through the
+//!   [`log`](https://docs.rs/log) crate. Tokens are never logged.
+//!
+//! There is no build without JSON support. easydb.io wraps every value, strings included, in a
+//! JSON body, so `serde` and `serde_json` are needed even for [`get`][EasyDB::get] and
+//! [`put`][EasyDB::put], and gating the `_json` methods off wouldn't remove either dependency.
+//! The smallest build is the default one: `reqwest` for HTTP, `http` for the simulated
+//! responses of dry runs, `httpdate` for reading the server's clock, `serde` and `serde_json`
+//! for request and response bodies, `toml` for `easydb.toml`, `error-chain` for errors,
+//! `crc32fast` for checksums, and `rand` for retry jitter and request IDs.
+//!
+
+#[macro_use]
+mod logging;
 
+mod batch;
+mod builder;
+mod checksum;
+mod clock;
+mod codec;
+mod config;
 mod easydb;
+mod health;
+mod key;
+mod ordered;
+mod outcome;
+mod paged;
+mod rate_limit;
+mod retry;
+mod semaphore;
+mod stats;
+mod sync;
+mod watch;
+pub use crate::batch::{BatchReport, OnConflict};
+pub use crate::builder::{EasyDBBuilder, MissingKeyBehavior, RequestOptions, TrailingSlash};
+pub use crate::clock::ClockOffset;
+pub use crate::codec::JsonCodec;
+pub use crate::config::EasyDBConfig;
 pub use crate::easydb::EasyDB;
+pub use crate::health::{Health, HealthStatus};
+pub use crate::key::Key;
+pub use crate::outcome::WriteOutcome;
+pub use crate::paged::PagedView;
+pub use crate::retry::RetryContext;
+pub use crate::stats::{DbStats, TypeCounts};
+pub use crate::sync::SyncReport;
+pub use crate::watch::WatchHandle;
 
 /// Re-exported [`Value`](https://docs.serde.rs/serde_json/enum.Value.html) type from serde_json.
 pub use crate::easydb::Json;
 
+/// Re-exported [`Url`](https://docs.rs/reqwest/0.9/reqwest/struct.Url.html) type from reqwest, for
+/// use with [`from_parts`][EasyDB::from_parts].
+pub use reqwest::Url;
+/// Re-exported [`Method`](https://docs.rs/reqwest/0.9/reqwest/struct.Method.html) and
+/// [`Response`](https://docs.rs/reqwest/0.9/reqwest/struct.Response.html) types from reqwest, for
+/// use with [`raw_request`][EasyDB::raw_request].
+pub use reqwest::{Method, Response};
+
 pub mod errors;
+pub mod prelude;
+
+mod mock;
 
 // Note that in order to run tests, you must create an `easydb.toml` in the current directory.
 #[cfg(test)]
 mod tests {
-	use crate::{errors::EdbResult, EasyDB};
+	use crate::{
+		errors::{EdbError, EdbErrorKind, EdbResult},
+		mock::{MockServer, Response},
+		BatchReport, EasyDB, EasyDBBuilder, Json, OnConflict, RequestOptions, WriteOutcome,
+	};
+	use serde::Serialize;
 	use serde_json::json;
+
+	fn mock_edb() -> EdbResult<(MockServer, EasyDB)> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).build()?;
+		Ok((server, edb))
+	}
+
+	fn mock_builder(server: &MockServer) -> EasyDBBuilder {
+		EasyDB::builder()
+			.uuid(crate::mock::UUID.to_string())
+			.token(crate::mock::TOKEN.to_string())
+			.url(server.url().to_string())
+	}
 	#[test]
+	#[allow(clippy::unnecessary_get_then_check)]
 	fn list() -> EdbResult<()> {
 		let edb = EasyDB::new()?;
 		edb.clear()?;
@@ -160,4 +255,2075 @@ mod tests {
 		);
 		Ok(())
 	}
+	#[test]
+	fn clear_count() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		for i in 0..5 {
+			edb.put(format!("key{}", i), "value")?;
+		}
+		assert_eq!(edb.clear_count()?, 5);
+		assert!(server.store().is_empty());
+		assert_eq!(edb.clear_count()?, 0);
+		Ok(())
+	}
+	#[test]
+	fn builder() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let builder = mock_builder(&server);
+		let edb = builder.clone().max_idle_per_host(0).build()?;
+		edb.put("hello", "world")?;
+		assert_eq!(&edb.get("hello")?, "world");
+		// The mock only speaks HTTP/1.1, so a client built for HTTP/2 can't talk to it.
+		let edb = builder.http2_prior_knowledge(true).build()?;
+		assert!(edb.get("hello").is_err());
+		assert!(EasyDB::builder().uuid("a".to_string()).build().is_err());
+		Ok(())
+	}
+	#[test]
+	fn owned_keys() -> EdbResult<()> {
+		let (_server, edb) = mock_edb()?;
+		let key = String::from("hello");
+		edb.put(key.clone(), "world")?;
+		assert_eq!(&edb.get(key.clone())?, "world");
+		assert_eq!(
+			edb.get_json(std::borrow::Cow::from("hello"))?,
+			json!("world")
+		);
+		edb.delete(key.clone())?;
+		assert_eq!(&edb.get(key)?, "");
+		Ok(())
+	}
+	#[test]
+	fn put_serializable() -> EdbResult<()> {
+		#[derive(Serialize)]
+		struct Point {
+			x: i32,
+			y: i32,
+		}
+		let (server, edb) = mock_edb()?;
+		edb.put_json("point", Point { x: 1, y: 2 })?;
+		assert_eq!(server.store()["point"], json!({"x": 1, "y": 2}));
+		let mut bad = std::collections::HashMap::new();
+		bad.insert(vec![1], 1);
+		assert!(edb.put_json("bad", bad).is_err());
+		assert!(!server.store().contains_key("bad"));
+		Ok(())
+	}
+	#[test]
+	fn raw_request() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("hello", json!("world"));
+		let mut resp = edb.raw_request(crate::Method::GET, Some("hello"), None)?;
+		assert_eq!(resp.status().as_u16(), 200);
+		assert_eq!(resp.text()?, r#""world""#);
+		let mut resp = edb.raw_request(crate::Method::GET, None, None)?;
+		assert_eq!(resp.json::<crate::Json>()?, json!({"hello": "world"}));
+		let request = &server.requests()[0];
+		assert_eq!(request.header("token"), Some(crate::mock::TOKEN));
+		Ok(())
+	}
+	#[test]
+	fn pretty_bodies() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let builder = mock_builder(&server);
+		builder.clone().build()?.put("compact", "a")?;
+		builder.pretty_bodies(true).build()?.put("pretty", "b")?;
+		let requests = server.requests();
+		assert_eq!(requests[0].body, br#"{"value":"a"}"#);
+		assert_eq!(requests[1].body, b"{\n  \"value\": \"b\"\n}");
+		assert_eq!(&server.store()["pretty"], "b");
+		Ok(())
+	}
+	#[test]
+	fn content_type() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let builder = mock_builder(&server);
+		builder.clone().build()?.put("default", "a")?;
+		let custom = "application/vnd.easydb+json; charset=utf-8";
+		builder
+			.content_type(custom.to_string())
+			.build()?
+			.put("custom", "b")?;
+		let requests = server.requests();
+		assert_eq!(requests[0].header("Content-Type"), Some("application/json"));
+		assert_eq!(requests[1].header("Content-Type"), Some(custom));
+		assert_eq!(&server.store()["custom"], "b");
+		Ok(())
+	}
+	#[test]
+	fn stale_reads() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).detect_stale_reads(true).build()?;
+		server.insert("key", json!("old"));
+		let mut stale_reads = 1;
+		server.handle(move |request| {
+			if request.method == "GET" && stale_reads > 0 {
+				stale_reads -= 1;
+				Some(Response::json(200, &json!("old")))
+			} else {
+				None
+			}
+		});
+		edb.put("key", "new")?;
+		match edb.get("key") {
+			Err(e) => match e.kind() {
+				EdbErrorKind::StaleRead(key, value) => {
+					assert_eq!(key, "key");
+					assert_eq!(value, &json!("old"));
+				}
+				kind => panic!("unexpected error: {}", kind),
+			},
+			Ok(value) => panic!("expected a stale read, got {}", value),
+		}
+		assert_eq!(&edb.get("key")?, "new");
+		// Once the write has been read back, other changes aren't reported.
+		server.insert("key", json!("changed elsewhere"));
+		assert_eq!(&edb.get("key")?, "changed elsewhere");
+		Ok(())
+	}
+	#[test]
+	fn reset_to() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("same", json!(1));
+		server.insert("changed", json!("old"));
+		server.insert("removed", json!(true));
+		let mut desired = std::collections::HashMap::new();
+		desired.insert("same".to_string(), json!(1));
+		desired.insert("changed".to_string(), json!("new"));
+		desired.insert("added".to_string(), json!({"a": "b"}));
+		let report = edb.reset_to(desired.clone())?;
+		assert_eq!(report.added, ["added"]);
+		assert_eq!(report.updated, ["changed"]);
+		assert_eq!(report.removed, ["removed"]);
+		assert_eq!(edb.list_json()?, desired);
+		assert!(edb.reset_to(desired)?.is_empty());
+		Ok(())
+	}
+	#[test]
+	fn key_too_long() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).build()?;
+		let key = "k".repeat(5000);
+		match edb.put(&key, "value").map_err(|e| e.0) {
+			Err(EdbErrorKind::KeyTooLong(len, 2000)) => assert!(len > 5000),
+			result => panic!("expected KeyTooLong, got {:?}", result),
+		}
+		assert!(server.requests().is_empty());
+		let edb = mock_builder(&server).max_key_url_len(10_000).build()?;
+		edb.put(&key, "value")?;
+		assert_eq!(&edb.get(&key)?, "value");
+		Ok(())
+	}
+	#[test]
+	fn retries() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let mut failures = 2;
+		server.handle(move |_| {
+			if failures > 0 {
+				failures -= 1;
+				Some(Response::new(503, "Service Unavailable"))
+			} else {
+				None
+			}
+		});
+		let builder = mock_builder(&server).retry_backoff(std::time::Duration::from_millis(1));
+		assert_eq!(
+			builder.clone().max_retries(1).build()?.put("key", "a")?,
+			WriteOutcome::ServerError(503)
+		);
+		assert_eq!(
+			builder.max_retries(3).build()?.put("key", "b")?,
+			WriteOutcome::Updated
+		);
+		assert_eq!(server.requests().len(), 3);
+		assert_eq!(&server.store()["key"], "b");
+		Ok(())
+	}
+	#[test]
+	fn as_json() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		assert_eq!(edb.as_json()?, json!({}));
+		server.insert("hello", json!("world"));
+		server.insert("nested", json!({"a": [1, 2]}));
+		assert_eq!(
+			edb.as_json()?,
+			json!({
+				"hello": "world",
+				"nested": {"a": [1, 2]}
+			})
+		);
+		Ok(())
+	}
+	fn import_server() -> EdbResult<(MockServer, EasyDB)> {
+		let (server, edb) = mock_edb()?;
+		server.insert("existing", json!("old"));
+		server.insert("untouched", json!("kept"));
+		Ok((server, edb))
+	}
+
+	const IMPORT: &[u8] = br#"{"existing": "new", "added": [1, 2]}"#;
+
+	#[test]
+	fn import_overwrite() -> EdbResult<()> {
+		let (server, edb) = import_server()?;
+		let report = edb.import_json(IMPORT, OnConflict::Overwrite)?;
+		assert_eq!(report.succeeded, ["added", "existing"]);
+		assert!(report.skipped.is_empty() && report.is_success());
+		let store = server.store();
+		assert_eq!(store["existing"], json!("new"));
+		assert_eq!(store["added"], json!([1, 2]));
+		assert_eq!(store["untouched"], json!("kept"));
+		Ok(())
+	}
+	#[test]
+	fn import_skip() -> EdbResult<()> {
+		let (server, edb) = import_server()?;
+		let report = edb.import_json(IMPORT, OnConflict::Skip)?;
+		assert_eq!(report.succeeded, ["added"]);
+		assert_eq!(report.skipped, ["existing"]);
+		let store = server.store();
+		assert_eq!(store["existing"], json!("old"));
+		assert_eq!(store["added"], json!([1, 2]));
+		Ok(())
+	}
+	#[test]
+	fn import_error() -> EdbResult<()> {
+		let (server, edb) = import_server()?;
+		match edb.import_json(IMPORT, OnConflict::Error).map_err(|e| e.0) {
+			Err(EdbErrorKind::ImportConflict(keys)) => assert_eq!(keys, ["existing"]),
+			result => panic!("expected ImportConflict, got {:?}", result),
+		}
+		assert!(!server.store().contains_key("added"));
+		assert!(edb.import_json(&b"[1, 2]"[..], OnConflict::Error).is_err());
+		Ok(())
+	}
+	#[test]
+	fn to_builder() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.insert("slow", json!("value"));
+		server.handle(|_| {
+			std::thread::sleep(std::time::Duration::from_millis(300));
+			None
+		});
+		let edb = mock_builder(&server)
+			.timeout(std::time::Duration::from_millis(50))
+			.build()?;
+		assert!(edb.get("slow").is_err());
+		let patient = edb
+			.to_builder()
+			.timeout(std::time::Duration::from_secs(5))
+			.build()?;
+		assert_eq!(patient.uuid(), edb.uuid());
+		assert_eq!(patient.token(), edb.token());
+		assert_eq!(patient.url(), edb.url());
+		assert_eq!(&patient.get("slow")?, "value");
+		Ok(())
+	}
+	#[test]
+	fn redirects() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.insert("target", json!("moved here"));
+		let location = format!("{}{}/target", server.url(), crate::mock::UUID);
+		server.handle(move |request| match request.key() {
+			Some("source") => Some(Response::new(302, "").header("Location", &location)),
+			_ => None,
+		});
+		assert_eq!(&mock_builder(&server).build()?.get("source")?, "moved here");
+		let edb = mock_builder(&server).max_redirects(0).build()?;
+		let resp = edb.raw_request(crate::Method::GET, Some("source"), None)?;
+		assert_eq!(resp.status().as_u16(), 302);
+		assert!(edb.get("source").is_err());
+		Ok(())
+	}
+	#[test]
+	fn find_keys_with_value() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("a", json!({"status": "active"}));
+		server.insert("b", json!({"status": "inactive"}));
+		server.insert("c", json!({"status": "active"}));
+		server.insert("d", json!("active"));
+		assert_eq!(
+			edb.find_keys_with_value(|v| v["status"] == "active")?,
+			["a", "c"]
+		);
+		assert!(edb.find_keys_with_value(|v| v.is_number())?.is_empty());
+		Ok(())
+	}
+	#[test]
+	fn request_options() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.insert("slow", json!("value"));
+		let mut failures = 2;
+		server.handle(move |request| {
+			std::thread::sleep(std::time::Duration::from_millis(300));
+			if request.method == "POST" && failures > 0 {
+				failures -= 1;
+				Some(Response::new(500, ""))
+			} else {
+				None
+			}
+		});
+		let edb = mock_builder(&server)
+			.timeout(std::time::Duration::from_millis(50))
+			.retry_backoff(std::time::Duration::from_millis(1))
+			.build()?;
+		assert!(edb.get("slow").is_err());
+		let opts = RequestOptions {
+			timeout: Some(std::time::Duration::from_secs(5)),
+			..RequestOptions::default()
+		};
+		assert_eq!(&edb.get_with_opts("slow", &opts)?, "value");
+		assert_eq!(edb.put_with_opts("slow", "new", &opts)?.status(), 500);
+		let opts = RequestOptions {
+			max_retries: Some(1),
+			..opts
+		};
+		assert!(edb.put_with_opts("slow", "new", &opts)?.is_success());
+		assert_eq!(&server.store()["slow"], "new");
+		assert_eq!(server.requests().len(), 5);
+		Ok(())
+	}
+	#[test]
+	fn list_ordered() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).build()?;
+		server.handle(|request| match request.key() {
+			None => Some(Response::new(
+				200,
+				r#"{"b": 1, "a": {"y": 2, "x": 3}, "c": 4}"#,
+			)),
+			Some(_) => None,
+		});
+		let keys: Vec<_> = edb.list_ordered()?.into_iter().map(|(k, _)| k).collect();
+		assert_eq!(keys, ["b", "a", "c"]);
+		let mut first = Vec::new();
+		let mut second = Vec::new();
+		assert_eq!(edb.export_json(&mut first)?, 3);
+		edb.export_json(&mut second)?;
+		assert_eq!(first, second);
+		let exported = String::from_utf8(first).unwrap();
+		assert!(exported.find("\"b\"") < exported.find("\"a\""));
+		assert!(exported.find("\"a\"") < exported.find("\"c\""));
+		Ok(())
+	}
+	#[test]
+	fn from_config_dir_in() -> EdbResult<()> {
+		let base = std::env::temp_dir().join(format!("easydb-config-{}", std::process::id()));
+		std::fs::create_dir_all(base.join("easydb"))?;
+		std::fs::write(
+			base.join("easydb").join("easydb.toml"),
+			"UUID = \"from-config-dir\"\nToken = \"secret\"\n",
+		)?;
+		let edb = EasyDB::from_config_dir_in(&base);
+		std::fs::remove_dir_all(&base)?;
+		let edb = edb?;
+		assert_eq!(edb.uuid(), "from-config-dir");
+		assert_eq!(edb.token(), "secret");
+		Ok(())
+	}
+	#[test]
+	fn try_from_path() -> EdbResult<()> {
+		use std::{convert::TryFrom, path::Path};
+		let dir = std::env::temp_dir().join(format!("easydb-try-from-{}", std::process::id()));
+		std::fs::create_dir_all(&dir)?;
+		let valid = dir.join("valid.toml");
+		let malformed = dir.join("malformed.toml");
+		std::fs::write(&valid, "UUID = \"from-path\"\nToken = \"secret\"\n")?;
+		std::fs::write(&malformed, "UUID = \n")?;
+		let results = (
+			EasyDB::try_from(valid.as_path()),
+			EasyDB::try_from(malformed.as_path()),
+			EasyDB::try_from(dir.join("missing.toml").as_path()),
+		);
+		std::fs::remove_dir_all(&dir)?;
+		assert_eq!(results.0?.uuid(), "from-path");
+		assert!(matches!(
+			results.1.map_err(|e| e.0),
+			Err(EdbErrorKind::Fs(_))
+		));
+		match results.2.map_err(|e| e.0) {
+			Err(EdbErrorKind::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+			result => panic!("expected a NotFound error, got {:?}", result),
+		}
+
+		let edb = EasyDB::try_from("UUID = \"from-str\"\nToken = \"secret\"")?;
+		assert_eq!(edb.token(), "secret");
+		assert!(EasyDB::try_from(Path::new("UUID = \"x\"\nToken = \"y\"")).is_err());
+		Ok(())
+	}
+	#[test]
+	fn write_outcome() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.handle(|request| {
+			let status = request.key()?.parse().ok()?;
+			Some(Response::new(status, ""))
+		});
+		let cases = [
+			("201", WriteOutcome::Created),
+			("200", WriteOutcome::Updated),
+			("401", WriteOutcome::Unauthorized),
+			("429", WriteOutcome::RateLimited),
+			("503", WriteOutcome::ServerError(503)),
+			("204", WriteOutcome::Other(204)),
+			("404", WriteOutcome::Other(404)),
+		];
+		for (status, expected) in cases.iter() {
+			let outcome = edb.put(status, "value")?;
+			assert_eq!(outcome, *expected);
+			assert_eq!(outcome.status().to_string(), *status);
+			assert_eq!(outcome.is_success(), status.starts_with('2'));
+		}
+		assert_eq!(edb.delete("401")?, WriteOutcome::Unauthorized);
+		Ok(())
+	}
+	#[test]
+	fn put_many() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let entries: Vec<_> = (0..40)
+			.map(|i| (format!("key{:02}", i), json!(i)))
+			.collect();
+		let report = edb.put_many(entries.clone(), 8);
+		assert!(report.is_success());
+		let keys: Vec<_> = entries.iter().map(|(key, _)| key.clone()).collect();
+		assert_eq!(report.succeeded, keys);
+		let list = edb.list_json()?;
+		assert_eq!(list.len(), 40);
+		for (key, value) in &entries {
+			assert_eq!(&list[key], value);
+		}
+
+		server.handle(|request| match request.key() {
+			Some("bad") => Some(Response::new(500, "")),
+			_ => None,
+		});
+		let report = edb.put_many(vec![("good", "a"), ("bad", "b")], 0);
+		assert_eq!(report.succeeded, ["good"]);
+		assert_eq!(report.failed.len(), 1);
+		assert_eq!(report.failed[0].0, "bad");
+		assert!(edb.put_many(Vec::<(String, Json)>::new(), 4).is_success());
+		Ok(())
+	}
+	#[test]
+	fn missing_keys() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.handle(|request| match request.key() {
+			Some("not-found") => Some(Response::new(404, "Not Found")),
+			Some("empty-body") => Some(Response::new(200, "")),
+			_ => None,
+		});
+		server.insert("set", json!("value"));
+		server.insert("object", json!({"a": 1}));
+		for key in &["not-found", "empty-body", "never-set"] {
+			assert_eq!(edb.get(key)?, "");
+			assert_eq!(edb.get_json(key)?, json!(""));
+			assert_eq!(edb.try_get(key)?, None);
+			assert_eq!(edb.try_get_json(key)?, None);
+		}
+		assert_eq!(edb.try_get("set")?, Some("value".to_string()));
+		assert_eq!(edb.try_get_json("object")?, Some(json!({"a": 1})));
+		assert!(edb.try_get("object").is_err());
+		Ok(())
+	}
+	#[test]
+	fn map_values() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("a", json!({"name": "a"}));
+		server.insert("b", json!({"name": "b", "version": 1}));
+		server.insert("plain", json!("not an object"));
+		let report = edb.map_values(|_, mut value| {
+			if let Some(object) = value.as_object_mut() {
+				object.insert("version".to_string(), json!(2));
+			}
+			value
+		})?;
+		assert_eq!(report.succeeded, ["a", "b"]);
+		assert_eq!(report.skipped, ["plain"]);
+		assert!(report.is_success());
+		let store = server.store();
+		assert_eq!(store["a"], json!({"name": "a", "version": 2}));
+		assert_eq!(store["b"], json!({"name": "b", "version": 2}));
+		assert_eq!(store["plain"], json!("not an object"));
+		Ok(())
+	}
+	#[test]
+	fn list_strings_lossy() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("string", json!("a"));
+		server.insert("number", json!(1));
+		server.insert("object", json!({"b": "c"}));
+		server.insert("empty", json!(""));
+		assert!(edb.list().is_err());
+		let mut skipped = vec!["earlier".to_string()];
+		let map = edb.list_strings_lossy(&mut skipped)?;
+		assert_eq!(map.len(), 2);
+		assert_eq!(map["string"], "a");
+		assert_eq!(map["empty"], "");
+		assert_eq!(skipped, ["earlier", "number", "object"]);
+		Ok(())
+	}
+	#[test]
+	fn delete_where() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("old1", json!({"archived": true}));
+		server.insert("old2", json!({"archived": true, "name": "x"}));
+		server.insert("new", json!({"archived": false}));
+		server.insert("plain", json!("archived"));
+		server.insert("skip-me", json!({"archived": true}));
+		let deleted =
+			edb.delete_where(|key, value| key != "skip-me" && value["archived"] == true)?;
+		assert_eq!(deleted, ["old1", "old2"]);
+		let mut remaining: Vec<_> = server.store().into_keys().collect();
+		remaining.sort();
+		assert_eq!(remaining, ["new", "plain", "skip-me"]);
+		Ok(())
+	}
+	#[test]
+	fn timed() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let delay = std::time::Duration::from_millis(50);
+		server.handle(move |request| {
+			if request.key() == Some("slow") {
+				std::thread::sleep(delay);
+			}
+			None
+		});
+		let (outcome, took) = edb.put_timed("slow", "value")?;
+		assert!(outcome.is_success());
+		assert!(took >= delay);
+		let (value, took) = edb.get_timed("slow")?;
+		assert_eq!(value, "value");
+		assert!(took >= delay);
+		let (_, took) = edb.get_json_timed("fast")?;
+		assert!(took > std::time::Duration::from_secs(0));
+		let (list, _) = edb.list_timed()?;
+		assert_eq!(list["slow"], "value");
+		Ok(())
+	}
+	#[test]
+	fn from_parts() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let url: crate::Url = server.url().parse()?;
+		let client = reqwest::Client::new();
+		let edb = EasyDB::from_parts(
+			crate::mock::UUID.to_string(),
+			crate::mock::TOKEN.to_string(),
+			url.clone(),
+			client.clone(),
+		)?;
+		assert_eq!(edb.url(), server.url());
+		edb.put("key", "value")?;
+		assert_eq!(edb.get("key")?, "value");
+		let bad = EasyDB::from_parts("http://[".to_string(), "token".to_string(), url, client);
+		assert!(bad.is_err());
+		assert!("UUID = \"a\"\nToken = \"b\"\nURL = \"not a url\""
+			.parse::<EasyDB>()
+			.is_err());
+		Ok(())
+	}
+	#[test]
+	fn from_env() -> EdbResult<()> {
+		let dir = std::env::temp_dir().join(format!("easydb-env-{}", std::process::id()));
+		std::fs::create_dir_all(&dir)?;
+		let token_file = dir.join("token");
+		std::fs::write(&token_file, "from-file\n")?;
+		let token_path = token_file.to_str().unwrap().to_string();
+		let missing_path = dir.join("missing").to_str().unwrap().to_string();
+		let vars = |pairs: &[(&str, &str)]| {
+			let map: std::collections::HashMap<_, _> = pairs
+				.iter()
+				.map(|(k, v)| (k.to_string(), v.to_string()))
+				.collect();
+			move |name: &str| map.get(name).cloned()
+		};
+
+		let edb = EasyDB::from_vars(vars(&[
+			("EASYDB_UUID", "uuid"),
+			("EASYDB_TOKEN", "from-var"),
+			("EASYDB_TOKEN_FILE", &token_path),
+		]));
+		let direct = EasyDB::from_vars(vars(&[
+			("EASYDB_UUID", "uuid"),
+			("EASYDB_TOKEN", "from-var"),
+			("EASYDB_URL", "http://localhost/database/"),
+		]));
+		let unreadable = EasyDB::from_vars(vars(&[
+			("EASYDB_UUID", "uuid"),
+			("EASYDB_TOKEN_FILE", &missing_path),
+		]));
+		std::fs::remove_dir_all(&dir)?;
+
+		let edb = edb?;
+		assert_eq!(edb.uuid(), "uuid");
+		assert_eq!(edb.token(), "from-file");
+		let direct = direct?;
+		assert_eq!(direct.token(), "from-var");
+		assert_eq!(direct.url(), "http://localhost/database/");
+		assert!(unreadable.is_err());
+		assert!(EasyDB::from_vars(vars(&[("EASYDB_UUID", "uuid")])).is_err());
+		Ok(())
+	}
+	#[test]
+	fn non_json_response() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let page = "<html><body><h1>502 Bad Gateway</h1></body></html>";
+		server.handle(move |request| match request.key() {
+			Some("html") | None => {
+				Some(Response::new(502, page).header("Content-Type", "text/html"))
+			}
+			Some("sniffed") => Some(Response::new(200, format!("\n  {}", page))),
+			Some("plain") => Some(Response::new(503, "Service Unavailable")),
+			_ => None,
+		});
+		for key in &["html", "sniffed"] {
+			match edb.get_json(key).map_err(|e| e.0) {
+				Err(EdbErrorKind::NonJsonResponse(_, snippet)) => assert_eq!(snippet, page),
+				result => panic!("expected NonJsonResponse, got {:?}", result),
+			}
+		}
+		match edb.get("plain").map_err(|e| e.0) {
+			Err(EdbErrorKind::NonJsonResponse(503, snippet)) => {
+				assert_eq!(snippet, "Service Unavailable")
+			}
+			result => panic!("expected NonJsonResponse, got {:?}", result),
+		}
+		match edb.list_json().map_err(|e| e.0) {
+			Err(EdbErrorKind::NonJsonResponse(502, _)) => {}
+			result => panic!("expected NonJsonResponse, got {:?}", result),
+		}
+		Ok(())
+	}
+	#[test]
+	fn put_upsert() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("existing", json!("old"));
+		assert!(edb.exists("existing")?);
+		assert!(!edb.exists("new")?);
+		assert_eq!(edb.put_upsert("new", "a")?, (true, WriteOutcome::Updated));
+		assert_eq!(
+			edb.put_upsert("existing", "b")?,
+			(false, WriteOutcome::Updated)
+		);
+		assert!(!edb.put_upsert("new", "c")?.0);
+		let store = server.store();
+		assert_eq!(store["new"], json!("c"));
+		assert_eq!(store["existing"], json!("b"));
+		Ok(())
+	}
+	#[test]
+	fn trim_keys() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let builder = mock_builder(&server);
+		builder.clone().build()?.put("\u{a0}key\n", "verbatim ")?;
+		let trimmed = builder.trim_keys(true).build()?;
+		trimmed.put("\u{a0}key\n", " value\n")?;
+		assert_eq!(trimmed.get("\u{3000}key ")?, " value\n");
+		let requests = server.requests();
+		assert_eq!(requests[0].key(), Some("%C2%A0key"));
+		assert_eq!(requests[1].key(), Some("key"));
+		assert_eq!(requests[2].key(), Some("key"));
+		let store = server.store();
+		assert_eq!(store["%C2%A0key"], json!("verbatim "));
+		assert_eq!(store["key"], json!(" value\n"));
+		Ok(())
+	}
+	#[test]
+	fn list_sorted() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		for key in &["m", "b", "z", "a", "q"] {
+			server.insert(key, json!(key.to_uppercase()));
+		}
+		let keys: Vec<_> = edb.list_sorted()?.into_keys().collect();
+		assert_eq!(keys, ["a", "b", "m", "q", "z"]);
+		let values: Vec<_> = edb.list_sorted_strings()?.into_values().collect();
+		assert_eq!(values, ["A", "B", "M", "Q", "Z"]);
+		server.insert("n", json!(1));
+		assert_eq!(edb.list_sorted()?["n"], json!(1));
+		assert!(edb.list_sorted_strings().is_err());
+		Ok(())
+	}
+	#[test]
+	fn put_raw_json() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let text = r#"{"a": [1, 2]}"#;
+		edb.put("put", text)?;
+		edb.put_json("put_json", text)?;
+		edb.put_json("put_json_value", serde_json::from_str::<crate::Json>(text)?)?;
+		edb.put_raw_json("put_raw_json", text)?;
+		let store = server.store();
+		assert_eq!(store["put"], json!(text));
+		assert_eq!(store["put_json"], json!(text));
+		assert_eq!(store["put_json_value"], json!({"a": [1, 2]}));
+		assert_eq!(store["put_raw_json"], json!({"a": [1, 2]}));
+		assert_eq!(edb.get("put")?, text);
+		assert!(edb.get("put_raw_json").is_err());
+
+		let requests = server.requests().len();
+		assert!(edb.put_raw_json("invalid", "{not json").is_err());
+		assert_eq!(server.requests().len(), requests);
+		Ok(())
+	}
+	#[test]
+	fn retry_jitter() {
+		use std::time::Duration;
+		let ms = Duration::from_millis;
+		let mut options = crate::builder::Options {
+			retry_jitter: false,
+			..Default::default()
+		};
+		let schedule: Vec<_> = (0..4).map(|attempt| options.retry_delay(attempt)).collect();
+		assert_eq!(schedule, [ms(100), ms(200), ms(400), ms(800)]);
+		// Huge attempts saturate instead of overflowing, with or without jitter.
+		options.retry_delay(100);
+
+		options.retry_jitter = true;
+		for attempt in 0..4 {
+			let delays: Vec<_> = (0..50).map(|_| options.retry_delay(attempt)).collect();
+			assert!(delays
+				.iter()
+				.all(|delay| *delay <= schedule[attempt as usize]));
+			assert!(delays.iter().any(|delay| *delay != delays[0]));
+		}
+		options.retry_delay(100);
+	}
+	#[test]
+	fn cancellation() -> EdbResult<()> {
+		use std::sync::{
+			atomic::{AtomicBool, Ordering},
+			Arc,
+		};
+		let (server, edb) = mock_edb()?;
+		for i in 0..5 {
+			server.insert(&format!("key{}", i), json!(i));
+		}
+		let cancel = Arc::new(AtomicBool::new(false));
+		let flag = cancel.clone();
+		let mut deletes = 0;
+		server.handle(move |request| {
+			if request.method == "DELETE" {
+				deletes += 1;
+				if deletes == 2 {
+					flag.store(true, Ordering::Relaxed);
+				}
+			}
+			None
+		});
+		match edb.clear_cancellable(&cancel).map_err(|e| e.0) {
+			Err(EdbErrorKind::Cancelled(done)) => assert_eq!(done, 2),
+			result => panic!("expected Cancelled, got {:?}", result),
+		}
+		assert_eq!(server.store().len(), 3);
+
+		let import = br#"{"a": 1, "b": 2}"#;
+		match edb.import_json_cancellable(&import[..], OnConflict::Overwrite, &cancel) {
+			Err(e) => assert!(matches!(e.kind(), EdbErrorKind::Cancelled(0))),
+			result => panic!("expected Cancelled, got {:?}", result),
+		}
+		assert!(edb
+			.reset_to_cancellable(Default::default(), &cancel)
+			.is_err());
+		assert_eq!(server.store().len(), 3);
+
+		cancel.store(false, Ordering::Relaxed);
+		assert_eq!(edb.clear_cancellable(&cancel)?, 3);
+		assert!(server.store().is_empty());
+		Ok(())
+	}
+	#[test]
+	fn get_or() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("present", json!({"max": 3}));
+		server.insert("name", json!("set"));
+		server.handle(|request| match request.key() {
+			Some("broken") => Some(Response::new(500, "")),
+			_ => None,
+		});
+		let default = json!({"max": 10});
+		assert_eq!(
+			edb.get_json_or("present", default.clone())?,
+			json!({"max": 3})
+		);
+		assert_eq!(edb.get_json_or("missing", default.clone())?, default);
+		assert!(edb.get_json_or("broken", default).is_err());
+		assert_eq!(edb.get_or("name", "default")?, "set");
+		assert_eq!(edb.get_or("missing", "default")?, "default");
+		assert!(edb.get_or("broken", "default").is_err());
+		Ok(())
+	}
+	#[test]
+	fn read_only() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.insert("key", json!("value"));
+		let edb = mock_builder(&server).read_only(true).build()?;
+		fn is_read_only<T>(result: EdbResult<T>) -> bool {
+			matches!(result.map_err(|e| e.0), Err(EdbErrorKind::ReadOnly))
+		}
+		assert!(is_read_only(edb.put("key", "new")));
+		assert!(is_read_only(edb.put_json("key", json!(1))));
+		assert!(is_read_only(edb.delete("key")));
+		assert!(is_read_only(edb.clear_count()));
+		assert!(is_read_only(edb.raw_request(
+			crate::Method::POST,
+			Some("key"),
+			None
+		)));
+		assert!(!edb.put_many(vec![("key", "new")], 1).is_success());
+		assert_eq!(edb.get("key")?, "value");
+		assert_eq!(edb.list()?.len(), 1);
+		let sent: Vec<_> = server.requests().into_iter().map(|r| r.method).collect();
+		assert!(sent.iter().all(|method| method == "GET"));
+		Ok(())
+	}
+	#[test]
+	fn error_constructors() -> EdbResult<()> {
+		use crate::errors::EdbError;
+		let message = EdbError::message(format!("Bad key {}", "x"));
+		assert!(matches!(message.kind(), EdbErrorKind::Msg(m) if m == "Bad key x"));
+		assert_eq!(message.to_string(), "Bad key x");
+		let status = EdbError::bad_status(418, "I'm a teapot".to_string());
+		assert!(matches!(status.kind(), EdbErrorKind::BadStatus(418, m) if m == "I'm a teapot"));
+		assert_eq!(
+			status.to_string(),
+			"Server returned status 418: I'm a teapot"
+		);
+
+		let (server, edb) = mock_edb()?;
+		server.handle(|_| Some(Response::new(503, "")));
+		let report = edb.put_many(vec![("key", "value")], 1);
+		match report.failed[0].1.kind() {
+			EdbErrorKind::BadStatus(503, reason) => assert_eq!(reason, "Service Unavailable"),
+			kind => panic!("expected BadStatus, got {:?}", kind),
+		}
+		Ok(())
+	}
+	#[test]
+	fn count_matching() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		for i in 0..3 {
+			server.insert(&format!("session:{}", i), json!({"user": i}));
+		}
+		server.insert("user:1", json!("a"));
+		server.insert("sessions", json!([1, 2]));
+		assert_eq!(edb.count_matching(|key| key.starts_with("session:"))?, 3);
+		assert_eq!(edb.count_matching(|_| true)?, 5);
+		assert_eq!(edb.count_matching(|key| key.is_empty())?, 0);
+		Ok(())
+	}
+	#[test]
+	fn json_codec() -> EdbResult<()> {
+		use crate::{Json, JsonCodec};
+		use std::sync::{
+			atomic::{AtomicUsize, Ordering},
+			Arc,
+		};
+		/// Delegates to serde_json, counting every call.
+		#[derive(Debug, Default)]
+		struct Counting {
+			encoded: AtomicUsize,
+			decoded: Arc<AtomicUsize>,
+		}
+		impl JsonCodec for Counting {
+			fn encode(&self, value: &Json) -> EdbResult<String> {
+				self.encoded.fetch_add(1, Ordering::Relaxed);
+				Ok(serde_json::to_string_pretty(value)?)
+			}
+			fn decode(&self, bytes: &[u8]) -> EdbResult<Json> {
+				self.decoded.fetch_add(1, Ordering::Relaxed);
+				Ok(serde_json::from_slice(bytes)?)
+			}
+		}
+
+		let server = MockServer::start()?;
+		let decoded = Arc::new(AtomicUsize::new(0));
+		let codec = Counting {
+			decoded: decoded.clone(),
+			..Counting::default()
+		};
+		let edb = mock_builder(&server).json_codec(codec).build()?;
+		edb.put_json("key", json!({"a": 1}))?;
+		assert_eq!(
+			server.requests()[0].body,
+			b"{\n  \"value\": {\n    \"a\": 1\n  }\n}"
+		);
+		assert_eq!(edb.get_json("key")?, json!({"a": 1}));
+		assert_eq!(edb.list_json()?["key"], json!({"a": 1}));
+		assert_eq!(decoded.load(Ordering::Relaxed), 2);
+		assert_eq!(edb.list_ordered()?.len(), 1);
+		assert_eq!(decoded.load(Ordering::Relaxed), 3);
+		Ok(())
+	}
+	#[test]
+	fn progress() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).build()?;
+		let mut calls = Vec::new();
+		let backup = r#"{"a": 1, "b": 2, "c": 3}"#;
+		edb.import_json_with_progress(backup.as_bytes(), OnConflict::Overwrite, |done, total| {
+			calls.push((done, total))
+		})?;
+		assert_eq!(calls, [(1, 3), (2, 3), (3, 3)]);
+
+		let mut calls = Vec::new();
+		let mut desired = std::collections::HashMap::new();
+		desired.insert("a".to_string(), json!(1));
+		desired.insert("d".to_string(), json!(4));
+		edb.reset_to_with_progress(desired, |done, total| calls.push((done, total)))?;
+		assert_eq!(calls, [(1, 3), (2, 3), (3, 3)]);
+
+		let mut calls = Vec::new();
+		assert_eq!(
+			edb.clear_with_progress(|done, total| calls.push((done, total)))?,
+			2
+		);
+		assert_eq!(calls, [(1, 2), (2, 2)]);
+		Ok(())
+	}
+	#[test]
+	fn trailing_whitespace() -> EdbResult<()> {
+		/// Only accepts bodies that are valid JSON with nothing after them.
+		#[derive(Debug)]
+		struct Strict;
+		impl crate::JsonCodec for Strict {
+			fn encode(&self, value: &Json) -> EdbResult<String> {
+				Ok(value.to_string())
+			}
+			fn decode(&self, bytes: &[u8]) -> EdbResult<Json> {
+				let value: Json = serde_json::from_slice(bytes)?;
+				assert_eq!(value.to_string().len(), bytes.len(), "untrimmed body");
+				Ok(value)
+			}
+		}
+
+		let server = MockServer::start()?;
+		server.handle(|req| match req.key() {
+			Some("value") => Some(Response::new(200, "{\"a\":1}\r\n \n")),
+			Some("blank") => Some(Response::new(200, "\n")),
+			_ => None,
+		});
+		let edb = mock_builder(&server).build()?;
+		assert_eq!(edb.get_json("value")?, json!({"a": 1}));
+		assert_eq!(edb.get_json("blank")?, json!(""));
+		let strict = mock_builder(&server).json_codec(Strict).build()?;
+		assert_eq!(strict.get_json("value")?, json!({"a": 1}));
+		Ok(())
+	}
+	#[test]
+	fn fallback_tokens() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.insert("key", json!("value"));
+		let edb = mock_builder(&server)
+			.token("old-token".to_string())
+			.fallback_tokens(vec!["wrong".to_string(), crate::mock::TOKEN.to_string()])
+			.build()?;
+		assert_eq!(edb.get("key")?, "value");
+		let tokens: Vec<_> = server
+			.requests()
+			.iter()
+			.map(|r| r.header("token").unwrap().to_string())
+			.collect();
+		assert_eq!(tokens, ["old-token", "wrong", crate::mock::TOKEN]);
+
+		let edb = mock_builder(&server)
+			.token("old-token".to_string())
+			.fallback_tokens(vec!["wrong".to_string()])
+			.build()?;
+		assert_eq!(edb.put("key", "new")?, WriteOutcome::Unauthorized);
+		Ok(())
+	}
+	#[test]
+	fn database_url() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let url = edb.database_url()?;
+		assert_eq!(url, format!("{}{}", server.url(), crate::mock::UUID));
+		edb.list()?;
+		assert!(url.ends_with(&server.requests()[0].path));
+		Ok(())
+	}
+	#[test]
+	fn iter() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("a", json!(1));
+		server.insert("b", json!("two"));
+		let mut seen = Vec::new();
+		for (key, value) in edb.iter()? {
+			seen.push((key, value));
+		}
+		seen.sort_by(|a, b| a.0.cmp(&b.0));
+		assert_eq!(
+			seen,
+			[("a".to_string(), json!(1)), ("b".to_string(), json!("two"))]
+		);
+		Ok(())
+	}
+	#[test]
+	fn trailing_slash() -> EdbResult<()> {
+		use crate::TrailingSlash;
+		let server = MockServer::start()?;
+		server.insert("key", json!("value"));
+		let url = server.url().trim_end_matches('/').to_string();
+		let edb = mock_builder(&server).url(url.clone()).build()?;
+		assert_eq!(edb.url(), server.url());
+		assert_eq!(edb.get("key")?, "value");
+
+		let result = mock_builder(&server)
+			.url(url)
+			.trailing_slash(TrailingSlash::Strict)
+			.build();
+		match result.unwrap_err().kind() {
+			EdbErrorKind::MissingTrailingSlash(u) => {
+				assert_eq!(u, server.url().trim_end_matches('/'))
+			}
+			other => panic!("unexpected error: {:?}", other),
+		}
+		mock_builder(&server)
+			.trailing_slash(TrailingSlash::Strict)
+			.build()?;
+		Ok(())
+	}
+	#[test]
+	fn objects() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let mut object = serde_json::Map::new();
+		object.insert("name".to_string(), json!("Ada"));
+		assert!(edb.put_object("user", object.clone())?.is_success());
+		assert_eq!(server.store()["user"], json!({"name": "Ada"}));
+		assert_eq!(edb.get_object("user")?, object);
+
+		server.insert("list", json!([1, 2]));
+		assert!(edb.get_object("list").is_err());
+		assert!(edb.get_object("missing").is_err());
+		Ok(())
+	}
+	#[test]
+	fn append_to_array() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		assert!(edb.append_to_array("new", json!(1))?.is_success());
+		assert_eq!(server.store()["new"], json!([1]));
+		server.insert("list", json!(["a"]));
+		edb.append_to_array("list", json!({"b": 2}))?;
+		assert_eq!(server.store()["list"], json!(["a", {"b": 2}]));
+
+		server.insert("object", json!({}));
+		assert!(edb.append_to_array("object", json!(1)).is_err());
+		assert_eq!(server.store()["object"], json!({}));
+		Ok(())
+	}
+	#[test]
+	fn key() -> EdbResult<()> {
+		use crate::Key;
+		for bad in [
+			"", ".", "..", "a/b", "a?b", "a#b", "a\\b", "a\nb", " a", "a\u{a0}", "user:1",
+		] {
+			match Key::new(bad).unwrap_err().kind() {
+				EdbErrorKind::InvalidKey(key, _) => assert_eq!(key, bad),
+				other => panic!("unexpected error for {:?}: {:?}", bad, other),
+			}
+		}
+		assert!(Key::new("x".repeat(Key::MAX_LEN + 1)).is_err());
+		let key = Key::new("1:user name")?;
+		assert_eq!(key.to_string(), "1:user name");
+
+		let (server, edb) = mock_edb()?;
+		server.insert("1:user%20name", json!("Ada"));
+		assert_eq!(edb.get(&key)?, "Ada");
+		assert_eq!(edb.get(key)?, "Ada");
+		Ok(())
+	}
+	#[test]
+	fn query_params() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("key", json!("value"));
+		let opts = RequestOptions {
+			query: vec![
+				("page".to_string(), "2".to_string()),
+				("fields".to_string(), "a b&c".to_string()),
+			],
+			..RequestOptions::default()
+		};
+		assert_eq!(edb.get_with_opts("key", &opts)?, "value");
+		assert_eq!(edb.list_json_with_opts(&opts)?.len(), 1);
+		let requests = server.requests();
+		let expected = format!("/database/{}/key?page=2&fields=a+b%26c", crate::mock::UUID);
+		assert_eq!(requests[0].path, expected);
+		let expected = format!("/database/{}?page=2&fields=a+b%26c", crate::mock::UUID);
+		assert_eq!(requests[1].path, expected);
+		Ok(())
+	}
+	#[test]
+	fn checksums() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).checksums(true).build()?;
+		let value = json!({"b": [1, 2.5], "a": "x"});
+		edb.put_json("key", &value)?;
+		let stored = server.store()["key"].clone();
+		assert_eq!(stored["v"], value);
+		assert!(stored["crc"].is_u64());
+		assert_eq!(edb.get_json("key")?, value);
+		assert_eq!(edb.list_json()?["key"], value);
+		assert_eq!(edb.get_json("missing")?, json!(""));
+
+		let mut tampered = stored;
+		tampered["v"]["a"] = json!("y");
+		server.insert("key", tampered);
+		server.insert("plain", json!("not wrapped"));
+		for key in &["key", "plain"] {
+			match edb.get_json(key).unwrap_err().kind() {
+				EdbErrorKind::ChecksumMismatch(k) => assert_eq!(k, key),
+				other => panic!("unexpected error: {:?}", other),
+			}
+		}
+		assert!(edb.list_json().is_err());
+		Ok(())
+	}
+	#[test]
+	fn get_many() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("a", json!(1));
+		server.insert("b", json!("two"));
+		server.insert("c", json!(3));
+		let keys = ["a", "b", "missing"];
+		let via_gets = edb.get_many(&keys)?;
+		let via_list = edb.get_many_via_list(&keys)?;
+		let mut expected = std::collections::HashMap::new();
+		expected.insert("a".to_string(), json!(1));
+		expected.insert("b".to_string(), json!("two"));
+		assert_eq!(via_gets, expected);
+		assert_eq!(via_list, expected);
+		assert_eq!(server.requests().len(), 4);
+		Ok(())
+	}
+	#[test]
+	fn json_location() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.handle(|_| Some(Response::new(200, "{\n  \"a\": 1,\n  \"b\": @\n}")));
+		let e = edb.get_json("key").unwrap_err();
+		assert_eq!(e.json_location(), Some((3, 8)));
+		assert_eq!(
+			crate::errors::EdbError::message("no JSON").json_location(),
+			None
+		);
+		Ok(())
+	}
+	#[test]
+	fn in_memory() -> EdbResult<()> {
+		let edb = EasyDB::in_memory()?;
+		assert_eq!(edb.get("missing")?, "");
+		assert_eq!(edb.try_get("missing")?, None);
+		edb.put("hello", "world")?;
+		edb.put_json("n", 1)?;
+		assert_eq!(edb.get("hello")?, "world");
+		assert_eq!(edb.list_json()?.len(), 2);
+		edb.delete("hello")?;
+		assert_eq!(edb.get("hello")?, "");
+		assert!(EasyDB::in_memory()?.list()?.is_empty());
+		Ok(())
+	}
+	#[test]
+	fn with_token() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server)
+			.token("other".to_string())
+			.fallback_tokens(vec![crate::mock::TOKEN.to_string()])
+			.build()?;
+		let derived = edb.with_token("derived".to_string());
+		assert_eq!(derived.put("key", "value")?, WriteOutcome::Unauthorized);
+		let requests = server.requests();
+		assert_eq!(requests.len(), 1);
+		assert_eq!(requests[0].header("token"), Some("derived"));
+		let derived = edb.with_token(crate::mock::TOKEN.to_string());
+		assert!(derived.put("key", "value")?.is_success());
+		Ok(())
+	}
+	#[test]
+	fn stats() -> EdbResult<()> {
+		use crate::{DbStats, TypeCounts};
+		let (server, edb) = mock_edb()?;
+		assert_eq!(edb.stats()?, DbStats::default());
+		server.insert("s", json!("abc"));
+		server.insert("n", json!(12));
+		server.insert("o", json!({"a": [1, 2]}));
+		server.insert("a", json!([true]));
+		server.insert("b", json!(false));
+		server.insert("z", json!(null));
+		server.insert("p", json!({"b": [3, 4]}));
+		let expected = DbStats {
+			key_count: 7,
+			total_bytes: 5 + 2 + 11 + 6 + 5 + 4 + 11,
+			largest: Some(("o".to_string(), 11)),
+			types: TypeCounts {
+				string: 1,
+				number: 1,
+				object: 2,
+				array: 1,
+				bool: 1,
+				null: 1,
+			},
+		};
+		assert_eq!(edb.stats()?, expected);
+		Ok(())
+	}
+	#[test]
+	fn gzip() -> EdbResult<()> {
+		use flate2::{write::GzEncoder, Compression};
+		use std::io::Write;
+		let (server, edb) = mock_edb()?;
+		let body = json!({"a": "x".repeat(1000)}).to_string();
+		let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+		encoder.write_all(body.as_bytes())?;
+		let compressed = encoder.finish()?;
+		assert!(compressed.len() < body.len());
+		server.handle(move |_| {
+			let resp = Response::new(200, compressed.clone());
+			Some(resp.header("Content-Encoding", "gzip"))
+		});
+		let mut written = Vec::new();
+		assert_eq!(edb.list_writer(&mut written)?, 200);
+		assert_eq!(written, body.as_bytes());
+		assert_eq!(edb.list_json()?["a"], json!("x".repeat(1000)));
+		assert_eq!(server.requests()[0].header("accept-encoding"), Some("gzip"));
+
+		let edb = mock_builder(&server).gzip(false).build()?;
+		let mut written = Vec::new();
+		edb.list_writer(&mut written)?;
+		assert_ne!(written, body.as_bytes());
+		assert_eq!(server.requests()[2].header("accept-encoding"), None);
+		Ok(())
+	}
+	#[test]
+	fn list_page() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		for i in 0..5 {
+			server.insert(&format!("k{}", 4 - i), json!(4 - i));
+		}
+		let keys = |page: Vec<(String, Json)>| -> Vec<String> {
+			page.into_iter().map(|(key, _)| key).collect()
+		};
+		assert_eq!(keys(edb.list_page(0, 2)?), ["k0", "k1"]);
+		assert_eq!(keys(edb.list_page(2, 2)?), ["k2", "k3"]);
+		assert_eq!(edb.list_page(4, 2)?, [("k4".to_string(), json!(4))]);
+		assert!(edb.list_page(6, 2)?.is_empty());
+		assert!(edb.list_page(0, 0)?.is_empty());
+		Ok(())
+	}
+	#[test]
+	fn paged() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		for i in 0..7 {
+			server.insert(&format!("k{}", i), json!(i));
+		}
+		let mut view = edb.paged(3)?;
+		server.insert("later", json!("not in the snapshot"));
+		assert_eq!(view.len(), 7);
+		assert_eq!(view.page_count(), 3);
+		assert!(view.prev().is_none());
+
+		let mut seen = Vec::new();
+		let mut sizes = Vec::new();
+		while let Some(page) = view.next() {
+			sizes.push(page.len());
+			seen.extend(page.iter().map(|(key, _)| key.clone()));
+		}
+		assert_eq!(sizes, [3, 3, 1]);
+		let expected: Vec<_> = (0..7).map(|i| format!("k{}", i)).collect();
+		assert_eq!(seen, expected);
+		assert_eq!(view.current(), Some(2));
+
+		assert_eq!(view.prev().unwrap()[0].0, "k3");
+		assert_eq!(view.prev().unwrap()[0].0, "k0");
+		assert!(view.prev().is_none());
+		assert_eq!(view.current(), Some(0));
+		assert_eq!(view.page(2).unwrap()[0].0, "k6");
+		assert!(view.page(3).is_none());
+		assert_eq!(view.current(), Some(2));
+		assert_eq!(server.requests().len(), 1);
+		Ok(())
+	}
+	#[test]
+	fn raw_bytes() -> EdbResult<()> {
+		use std::{
+			collections::HashMap,
+			sync::{Arc, Mutex},
+		};
+		let (server, edb) = mock_edb()?;
+		let blobs = Arc::new(Mutex::new(HashMap::new()));
+		let handler_blobs = blobs.clone();
+		server.handle(move |req| {
+			let key = req.key()?.to_string();
+			let mut blobs = handler_blobs.lock().unwrap();
+			match &req.method[..] {
+				"POST" if req.header("content-type") == Some("application/octet-stream") => {
+					blobs.insert(key, req.body.clone());
+					Some(Response::new(200, ""))
+				}
+				"GET" => match blobs.get(&key) {
+					Some(bytes) => Some(Response::new(200, bytes.clone())),
+					None => Some(Response::new(404, "")),
+				},
+				_ => Some(Response::new(415, "")),
+			}
+		});
+		let bytes = [0, 159, 146, 150, 255, b'\n'];
+		assert!(edb.put_raw_bytes("blob", &bytes)?.is_success());
+		assert_eq!(blobs.lock().unwrap()["blob"], bytes);
+		assert_eq!(edb.get_raw_bytes("blob")?, bytes);
+		assert!(edb.get_raw_bytes("missing")?.is_empty());
+		assert_eq!(edb.put_json("blob", 1)?.status(), 415);
+		Ok(())
+	}
+	#[test]
+	fn config() -> EdbResult<()> {
+		use crate::EasyDBConfig;
+		use std::convert::TryFrom;
+		let (server, edb) = mock_edb()?;
+		server.insert("key", json!("value"));
+		let config = edb.config();
+		assert_eq!(config.uuid, crate::mock::UUID);
+		assert_eq!(config.token, crate::mock::TOKEN);
+		assert_eq!(config.url, server.url());
+		assert!(!format!("{:?}", config).contains(crate::mock::TOKEN));
+
+		let text = toml::to_string(&edb.into_config()).unwrap();
+		let parsed: EasyDBConfig = toml::from_str(&text)?;
+		assert_eq!(parsed, config);
+		let edb: EasyDB = text.parse()?;
+		assert_eq!(edb.get("key")?, "value");
+		let edb = EasyDB::try_from(config)?;
+		assert_eq!(edb.get("key")?, "value");
+		Ok(())
+	}
+	#[test]
+	fn health() -> EdbResult<()> {
+		use crate::HealthStatus;
+		let (server, edb) = mock_edb()?;
+		let health = edb.health()?;
+		assert_eq!(health.status, HealthStatus::Healthy);
+		assert_eq!(health.http_status, Some(200));
+		assert!(health.is_healthy());
+
+		let health = edb.with_token("wrong".to_string()).health()?;
+		assert_eq!(health.status, HealthStatus::Unauthorized);
+		assert_eq!(health.http_status, Some(401));
+
+		server.handle(|_| Some(Response::new(503, "")));
+		let edb = mock_builder(&server).max_retries(3).build()?;
+		assert_eq!(edb.health()?.status, HealthStatus::Unhealthy);
+		assert_eq!(server.requests().len(), 3);
+
+		let port = std::net::TcpListener::bind("127.0.0.1:0")?
+			.local_addr()?
+			.port();
+		let edb = EasyDB::builder()
+			.uuid("uuid".to_string())
+			.token("token".to_string())
+			.url(format!("http://127.0.0.1:{}/database/", port))
+			.build()?;
+		let health = edb.health()?;
+		assert_eq!(health.status, HealthStatus::Unreachable);
+		assert_eq!(health.http_status, None);
+		assert!(health.error.is_some());
+		assert!(health.to_string().starts_with("Unreachable"));
+		Ok(())
+	}
+	#[test]
+	fn clear_best_effort() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		for key in &["a", "b", "c", "d"] {
+			server.insert(key, json!(1));
+		}
+		server.handle(|req| match (&req.method[..], req.key()) {
+			("DELETE", Some("b")) => Some(Response::new(500, "")),
+			_ => None,
+		});
+		let report = edb.clear_best_effort()?;
+		assert_eq!(report.succeeded, ["a", "c", "d"]);
+		assert_eq!(report.failed.len(), 1);
+		assert_eq!(report.failed[0].0, "b");
+		assert!(!report.is_success());
+		assert_eq!(server.store().into_keys().collect::<Vec<_>>(), ["b"]);
+		Ok(())
+	}
+	#[test]
+	fn typed_getters() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("bool", json!(true));
+		server.insert("int", json!(-3));
+		server.insert("float", json!(2.5));
+		server.insert("string", json!("text"));
+
+		assert_eq!(edb.get_bool("bool")?, Some(true));
+		assert_eq!(edb.get_i64("int")?, Some(-3));
+		assert_eq!(edb.get_f64("float")?, Some(2.5));
+		assert_eq!(edb.get_f64("int")?, Some(-3.0));
+		assert_eq!(edb.get_string("string")?, Some("text".to_string()));
+
+		assert_eq!(edb.get_bool("missing")?, None);
+		assert_eq!(edb.get_i64("missing")?, None);
+		assert_eq!(edb.get_f64("missing")?, None);
+		assert_eq!(edb.get_string("missing")?, None);
+
+		assert!(edb.get_bool("int").is_err());
+		assert!(edb.get_i64("float").is_err());
+		assert!(edb.get_f64("string").is_err());
+		assert!(edb.get_string("bool").is_err());
+		assert_eq!(
+			edb.get_i64("string").unwrap_err().to_string(),
+			"Value was not an i64"
+		);
+		Ok(())
+	}
+	#[test]
+	fn dry_run() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.insert("a", json!(1));
+		server.insert("b", json!(2));
+		let edb = mock_builder(&server)
+			.dry_run(true)
+			.detect_stale_reads(true)
+			.build()?;
+		assert_eq!(edb.put("a", "new")?, WriteOutcome::Updated);
+		assert!(edb.delete("b")?.is_success());
+		assert!(edb.put_raw_bytes("c", b"bytes")?.is_success());
+		let mut resp = edb.raw_request(crate::Method::DELETE, Some("a"), None)?;
+		assert_eq!(resp.status(), 200);
+		assert_eq!(resp.text()?, "");
+		assert_eq!(edb.clear_count()?, 0);
+		let mut calls = 0;
+		assert_eq!(edb.clear_with_progress(|_, _| calls += 1)?, 0);
+		assert_eq!(calls, 2);
+		assert!(edb.clear_best_effort()?.is_success());
+		assert!(server.requests().iter().all(|r| r.method == "GET"));
+
+		assert_eq!(edb.get_json("a")?, json!(1));
+		assert_eq!(server.store().len(), 2);
+		Ok(())
+	}
+
+	#[test]
+	fn batch_into_result() {
+		let mut report = BatchReport::default();
+		report.succeeded.push("a".to_string());
+		report.failed.push((
+			"b".to_string(),
+			EdbError::bad_status(500, "Internal Server Error".to_string()),
+		));
+		report
+			.failed
+			.push(("c".to_string(), EdbError::message("Timed out")));
+		let e = report.into_result().unwrap_err();
+		match e.kind() {
+			EdbErrorKind::Batch(failures) => {
+				let keys: Vec<_> = failures.iter().map(|(k, _)| &k[..]).collect();
+				assert_eq!(keys, ["b", "c"]);
+			}
+			kind => panic!("unexpected error {:?}", kind),
+		}
+		assert_eq!(
+			e.to_string(),
+			"2 keys failed: b (Server returned status 500: Internal Server Error), c (Timed out)"
+		);
+	}
+
+	#[test]
+	fn watch_channel() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("watched", json!("old"));
+		let (changes, handle) =
+			edb.watch_channel("watched", std::time::Duration::from_millis(10))?;
+		server.insert("watched", json!({"new": true}));
+		let value = changes
+			.recv_timeout(std::time::Duration::from_secs(5))
+			.unwrap();
+		assert_eq!(value, json!({"new": true}));
+		drop(handle);
+		// The poller notices the handle is gone and drops its sender.
+		while changes.recv_timeout(std::time::Duration::from_secs(5))
+			!= Err(std::sync::mpsc::RecvTimeoutError::Disconnected)
+		{}
+		Ok(())
+	}
+
+	#[test]
+	fn put_many_duplicates() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let entries = vec![("a", 1), ("b", 2), ("a", 3), ("c", 4), ("b", 5)];
+		let report = edb.put_many(entries, 2);
+		assert_eq!(report.succeeded, ["a", "c", "b"]);
+		let mut posted: Vec<_> = server
+			.requests()
+			.iter()
+			.filter(|r| r.method == "POST")
+			.map(|r| r.key().unwrap().to_string())
+			.collect();
+		posted.sort();
+		assert_eq!(posted, ["a", "b", "c"]);
+		assert_eq!(server.store()["a"], json!(3));
+		assert_eq!(server.store()["b"], json!(5));
+		Ok(())
+	}
+
+	#[test]
+	fn rate_limit() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).rate_limit(50.0).build()?;
+		let tenant = edb.with_token(crate::mock::TOKEN.to_string());
+		let start = std::time::Instant::now();
+		for i in 0..5 {
+			edb.put(format!("key{}", i), "value")?;
+			tenant.get(format!("key{}", i))?;
+		}
+		// The first of the 10 requests goes out at once, and each later one waits 20 ms.
+		assert!(start.elapsed() >= std::time::Duration::from_millis(180));
+		Ok(())
+	}
+
+	#[test]
+	fn get_response() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("a", json!({"big": "value"}));
+		server.handle(|request| match request.key() {
+			Some("teapot") => Some(Response::new(418, "short and stout")),
+			_ => None,
+		});
+		let mut resp = edb.get_response("a")?;
+		assert_eq!(resp.status().as_u16(), 200);
+		assert_eq!(resp.text()?, r#"{"big":"value"}"#);
+		let mut resp = edb.get_response("teapot")?;
+		assert_eq!(resp.status().as_u16(), 418);
+		assert_eq!(resp.text()?, "short and stout");
+		Ok(())
+	}
+
+	#[test]
+	fn put_object_entries() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let report = edb.put_object_entries(json!({"theme": "dark", "limits": {"max": 3}}))?;
+		assert!(report.is_success());
+		assert_eq!(server.store()["theme"], json!("dark"));
+		assert_eq!(server.store()["limits"], json!({"max": 3}));
+		assert_eq!(server.store().len(), 2);
+		assert!(edb.put_object_entries(json!([1, 2])).is_err());
+		assert_eq!(server.store().len(), 2);
+		Ok(())
+	}
+
+	#[test]
+	fn retry_on() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let mut failures = 2;
+		server.handle(move |_| {
+			if failures == 0 {
+				return None;
+			}
+			failures -= 1;
+			Some(Response::new(409, "Conflict"))
+		});
+		let edb = mock_builder(&server)
+			.max_retries(3)
+			.retry_backoff(std::time::Duration::from_millis(1))
+			.retry_on(|ctx| ctx.status() == Some(409) && ctx.attempt() <= 3)
+			.build()?;
+		assert!(edb.put("a", "b")?.is_success());
+		assert_eq!(server.requests().len(), 3);
+		assert_eq!(server.store()["a"], json!("b"));
+
+		// A 500 isn't retried when the predicate says no.
+		let server = MockServer::start()?;
+		server.handle(|_| Some(Response::new(500, "Internal Server Error")));
+		let edb = mock_builder(&server)
+			.max_retries(3)
+			.retry_on(|ctx| ctx.error().is_some())
+			.build()?;
+		assert!(!edb.put("a", "b")?.is_success());
+		assert_eq!(server.requests().len(), 1);
+		Ok(())
+	}
+
+	#[test]
+	fn from_toml_str_full() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = EasyDB::from_toml_str_full(&format!(
+			"UUID = \"{}\"\nToken = \"{}\"\nURL = \"{}\"\nTimeout = 0.5\nMaxRetries = 2\nRateLimit = 10\nReadOnly = true\n",
+			crate::mock::UUID,
+			crate::mock::TOKEN,
+			server.url()
+		))?;
+		assert_eq!(edb.url(), server.url());
+		assert!(matches!(
+			edb.put("a", "b").unwrap_err().kind(),
+			EdbErrorKind::ReadOnly
+		));
+		server.handle(|_| Some(Response::new(503, "Service Unavailable")));
+		edb.get_response("a")?;
+		assert_eq!(server.requests().len(), 3);
+
+		// Plain parsing ignores the settings.
+		let server = MockServer::start()?;
+		let edb: EasyDB = format!(
+			"UUID = \"{}\"\nToken = \"{}\"\nURL = \"{}\"\nReadOnly = true\n",
+			crate::mock::UUID,
+			crate::mock::TOKEN,
+			server.url()
+		)
+		.parse()?;
+		assert!(edb.put("a", "b")?.is_success());
+		assert!(EasyDB::from_toml_str_full("UUID = \"a\"\nToken = \"b\"\nTimeout = -1").is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn compact() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.insert("a", json!("old"));
+		server.insert("b", json!({"legacy": [1, 2]}));
+		let edb = mock_builder(&server).checksums(true).build()?;
+		assert!(edb.get_json("a").is_err());
+		let report = edb.compact(|_key, value| value)?;
+		assert_eq!(report.succeeded, ["a", "b"]);
+		assert_eq!(server.store()["a"]["v"], json!("old"));
+		assert!(server.store()["b"]["crc"].is_number());
+		assert_eq!(edb.get_json("b")?, json!({"legacy": [1, 2]}));
+		Ok(())
+	}
+
+	#[test]
+	fn client() -> EdbResult<()> {
+		let (_server, edb) = mock_edb()?;
+		let other = MockServer::start()?;
+		let resp = edb
+			.client()
+			.get(other.url())
+			.header("token", "other")
+			.send()?;
+		assert_eq!(resp.status().as_u16(), 401);
+		assert_eq!(other.requests()[0].header("token"), Some("other"));
+		Ok(())
+	}
+
+	#[test]
+	fn typed_puts() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		edb.put_bool("bool", false)?;
+		edb.put_i64("int", -42)?;
+		edb.put_f64("float", 2.5)?;
+		assert_eq!(server.store()["bool"], json!(false));
+		assert_eq!(server.store()["int"], json!(-42));
+		assert_eq!(edb.get_bool("bool")?, Some(false));
+		assert_eq!(edb.get_i64("int")?, Some(-42));
+		assert_eq!(edb.get_f64("float")?, Some(2.5));
+		assert!(edb.put_f64("nan", f64::NAN).is_err());
+		assert!(!server.store().contains_key("nan"));
+		Ok(())
+	}
+
+	#[test]
+	fn max_concurrency() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.set_delay(std::time::Duration::from_millis(30));
+		let edb = mock_builder(&server).max_concurrency(2).build()?;
+		let report = edb.put_many((0..8).map(|i| (format!("key{}", i), i)), 8);
+		assert!(report.is_success());
+		assert!(server.max_in_flight() <= 2);
+		assert_eq!(server.store().len(), 8);
+		Ok(())
+	}
+
+	#[test]
+	fn max_concurrency_covers_body() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.set_body_delay(std::time::Duration::from_millis(30));
+		for i in 0..8 {
+			server.insert(&format!("key{}", i), json!("x".repeat(100)));
+		}
+		let edb = mock_builder(&server).max_concurrency(2).build()?;
+		std::thread::scope(|scope| {
+			let handles: Vec<_> = (0..8)
+				.map(|i| {
+					let edb = &edb;
+					scope.spawn(move || edb.get(format!("key{}", i)))
+				})
+				.collect();
+			for handle in handles {
+				assert_eq!(handle.join().unwrap()?, "x".repeat(100));
+			}
+			Ok::<(), EdbError>(())
+		})?;
+		assert!(server.max_in_flight() <= 2);
+		Ok(())
+	}
+
+	#[test]
+	fn exists_head() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("big", json!({"value": "x".repeat(1000)}));
+		server.insert("small", json!(1));
+		assert!(edb.exists("big")?);
+		assert!(edb.exists("small")?);
+		assert!(!edb.exists("missing")?);
+		let methods: Vec<_> = server.requests().iter().map(|r| r.method.clone()).collect();
+		// Short bodies are confirmed with GET.
+		assert_eq!(methods, ["HEAD", "HEAD", "GET", "HEAD", "GET"]);
+
+		let (server, edb) = mock_edb()?;
+		server.insert("big", json!("x".repeat(1000)));
+		server.handle(|request| match &request.method[..] {
+			"HEAD" => Some(Response::new(405, "Method Not Allowed")),
+			_ => None,
+		});
+		assert!(edb.exists("big")?);
+		assert!(!edb.exists("missing")?);
+		assert_eq!(server.requests().len(), 4);
+		Ok(())
+	}
+
+	#[test]
+	fn try_list() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("name", json!("Ada"));
+		server.insert("count", json!(3));
+		server.insert("city", json!("London"));
+		server.insert("tags", json!(["a"]));
+		let (strings, skipped) = edb.try_list()?;
+		assert_eq!(strings.len(), 2);
+		assert_eq!(strings["name"], "Ada");
+		assert_eq!(strings["city"], "London");
+		assert_eq!(skipped, ["count", "tags"]);
+		Ok(())
+	}
+
+	#[test]
+	fn replace_all() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		server.insert("same", json!(1));
+		server.insert("changed", json!("old"));
+		server.insert("gone", json!(true));
+		let data: std::collections::HashMap<_, _> = vec![
+			("same".to_string(), json!(1)),
+			("changed".to_string(), json!("new")),
+			("added".to_string(), json!([1])),
+		]
+		.into_iter()
+		.collect();
+		let report = edb.replace_all(data.clone())?;
+		assert_eq!(report.added, ["added"]);
+		assert_eq!(report.updated, ["changed"]);
+		assert_eq!(report.removed, ["gone"]);
+		let writes: Vec<_> = server
+			.requests()
+			.iter()
+			.filter(|r| r.method != "GET")
+			.map(|r| format!("{} {}", r.method, r.key().unwrap()))
+			.collect();
+		assert_eq!(writes, ["POST added", "POST changed", "DELETE gone"]);
+		assert_eq!(server.store(), data);
+		Ok(())
+	}
+
+	#[test]
+	fn server_time_offset() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		let offset = edb.server_time_offset()?;
+		assert!(offset.abs() < std::time::Duration::from_secs(5));
+
+		let ahead = std::time::SystemTime::now() + std::time::Duration::from_secs(3600);
+		let date = httpdate::fmt_http_date(ahead);
+		server.handle(move |_| Some(Response::new(200, "{}").header("Date", &date)));
+		let offset = edb.server_time_offset()?;
+		assert!(matches!(offset, crate::ClockOffset::Ahead(_)));
+		assert!((offset.as_secs_f64() - 3600.0).abs() < 5.0);
+
+		server.handle(|_| Some(Response::new(200, "{}").header("Date", "yesterday")));
+		assert!(edb.server_time_offset().is_err());
+		Ok(())
+	}
+
+	#[test]
+	fn missing_key_behavior() -> EdbResult<()> {
+		use crate::MissingKeyBehavior;
+		let server = MockServer::start()?;
+		server.insert("set", json!("value"));
+		let edb = mock_builder(&server).build()?;
+		assert_eq!(edb.get_json("missing")?, json!(""));
+		assert_eq!(edb.get("missing")?, "");
+
+		let edb = mock_builder(&server)
+			.missing_key_behavior(MissingKeyBehavior::Null)
+			.build()?;
+		assert_eq!(edb.get_json("missing")?, Json::Null);
+		assert_eq!(edb.get("missing")?, "");
+		assert_eq!(edb.get_json("set")?, json!("value"));
+
+		let edb = mock_builder(&server)
+			.missing_key_behavior(MissingKeyBehavior::Error)
+			.build()?;
+		for e in [
+			edb.get_json("missing").unwrap_err(),
+			edb.get("missing").unwrap_err(),
+		] {
+			assert!(matches!(e.kind(), EdbErrorKind::MissingKey(key) if key == "missing"));
+		}
+		assert_eq!(edb.try_get_json("missing")?, None);
+		assert!(!edb.exists("missing")?);
+		assert_eq!(edb.get("set")?, "value");
+		Ok(())
+	}
+
+	#[test]
+	fn get_all_typed() -> EdbResult<()> {
+		#[derive(Debug, PartialEq, serde::Deserialize)]
+		struct User {
+			name: String,
+			age: u32,
+		}
+		let (server, edb) = mock_edb()?;
+		server.insert("user-2", json!({"name": "Grace", "age": 85}));
+		server.insert("user-1", json!({"name": "Ada", "age": 36}));
+		let users = edb.get_all_typed::<User>()?;
+		let expected = [
+			(
+				"user-1".to_string(),
+				User {
+					name: "Ada".to_string(),
+					age: 36,
+				},
+			),
+			(
+				"user-2".to_string(),
+				User {
+					name: "Grace".to_string(),
+					age: 85,
+				},
+			),
+		];
+		assert_eq!(users, expected);
+
+		server.insert("user-3", json!({"name": "Linus"}));
+		let e = edb.get_all_typed::<User>().unwrap_err();
+		assert_eq!(e.to_string(), "Value of key user-3 has the wrong type");
+		Ok(())
+	}
+
+	#[test]
+	fn request_id_header() -> EdbResult<()> {
+		let (server, edb) = mock_edb()?;
+		edb.get("a")?;
+		assert_eq!(server.requests()[0].header("x-request-id"), None);
+
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server)
+			.request_id_header("X-Request-Id".to_string())
+			.build()?;
+		edb.get("a")?;
+		edb.get("a")?;
+		let opts = RequestOptions {
+			request_id: Some("chosen".to_string()),
+			..RequestOptions::default()
+		};
+		edb.get_with_opts("a", &opts)?;
+		let ids: Vec<_> = server
+			.requests()
+			.iter()
+			.map(|r| r.header("x-request-id").unwrap().to_string())
+			.collect();
+		assert_eq!(ids[0].len(), 36);
+		assert_eq!(&ids[0][14..15], "4");
+		assert_ne!(ids[0], ids[1]);
+		assert_eq!(ids[2], "chosen");
+
+		assert!(mock_builder(&server)
+			.request_id_header("Bad Header".to_string())
+			.build()
+			.is_err());
+		Ok(())
+	}
+
+	/// Captures this crate's log messages. The logger is installed by the first call, since
+	/// there can only be one per process.
+	#[cfg(feature = "logging")]
+	fn captured_logs() -> &'static std::sync::Mutex<Vec<String>> {
+		struct Capture;
+		static LOGS: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+		impl log::Log for Capture {
+			fn enabled(&self, _: &log::Metadata) -> bool {
+				true
+			}
+			fn log(&self, record: &log::Record) {
+				if record.target().starts_with("easydb") {
+					LOGS.lock().unwrap().push(record.args().to_string());
+				}
+			}
+			fn flush(&self) {}
+		}
+		static CAPTURE: Capture = Capture;
+		if log::set_logger(&CAPTURE).is_ok() {
+			log::set_max_level(log::LevelFilter::Trace);
+		}
+		&LOGS
+	}
+
+	#[cfg(feature = "logging")]
+	#[test]
+	fn request_id_logged() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server)
+			.request_id_header("X-Request-Id".to_string())
+			.build()?;
+		let logs = captured_logs();
+		edb.get("logged")?;
+		let id = server.requests()[0]
+			.header("x-request-id")
+			.unwrap()
+			.to_string();
+		let line = logs
+			.lock()
+			.unwrap()
+			.iter()
+			.find(|line| line.contains("/logged"))
+			.cloned()
+			.unwrap();
+		assert!(line.starts_with(&format!("[{}] GET ", id)));
+		assert!(!line.contains(crate::mock::TOKEN));
+		Ok(())
+	}
+
+	#[test]
+	fn with_url() -> EdbResult<()> {
+		let (production, edb) = mock_edb()?;
+		let staging = MockServer::start()?;
+		let url = staging.url().trim_end_matches('/').to_string();
+		let edb_staging = edb.with_url(url)?;
+		assert_eq!(edb_staging.url(), staging.url());
+		assert_eq!(edb_staging.uuid(), edb.uuid());
+		edb_staging.put("a", "staged")?;
+		assert_eq!(staging.store()["a"], json!("staged"));
+		assert!(production.store().is_empty());
+
+		assert!(edb.with_url("not a url".to_string()).is_err());
+		let strict = mock_builder(&production)
+			.trailing_slash(crate::TrailingSlash::Strict)
+			.build()?;
+		let e = strict
+			.with_url("http://localhost/database".to_string())
+			.unwrap_err();
+		assert!(matches!(e.kind(), EdbErrorKind::MissingTrailingSlash(_)));
+		Ok(())
+	}
+	#[test]
+	fn per_call_timeout_with_custom_client() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		server.insert("key", json!("value"));
+		let client = reqwest::Client::builder().gzip(false).build()?;
+		let edb = EasyDB::from_parts(
+			crate::mock::UUID.to_string(),
+			crate::mock::TOKEN.to_string(),
+			server.url().parse()?,
+			client,
+		)?;
+		let opts = RequestOptions {
+			timeout: Some(std::time::Duration::from_secs(5)),
+			..RequestOptions::default()
+		};
+		assert!(edb.get_with_opts("key", &opts).is_err());
+		assert!(server.requests().is_empty());
+		assert_eq!(&edb.get("key")?, "value");
+		// A handle built from its own settings again can be rebuilt with another timeout.
+		let rebuilt = edb.to_builder().gzip(false).build()?;
+		assert_eq!(&rebuilt.get_with_opts("key", &opts)?, "value");
+		Ok(())
+	}
+	#[test]
+	fn checksums_ignore_key_order() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).checksums(true).build()?;
+		let crc = crc32fast::hash(br#"{"a":[{"x":1,"y":2}],"b":{"c":3,"d":{"e":4,"f":5}}}"#);
+		let body = format!(
+			r#"{{"crc": {}, "v": {{"b": {{"d": {{"f": 5, "e": 4}}, "c": 3}}, "a": [{{"y": 2, "x": 1}}]}}}}"#,
+			crc
+		);
+		server.handle(move |_| Some(Response::new(200, body.clone())));
+		let value = edb.get_json("key")?;
+		assert_eq!(
+			value,
+			json!({"a": [{"x": 1, "y": 2}], "b": {"c": 3, "d": {"e": 4, "f": 5}}})
+		);
+		Ok(())
+	}
+	#[test]
+	fn in_memory_stops_with_last_handle() -> EdbResult<()> {
+		let edb = EasyDB::in_memory()?;
+		let url = reqwest::Url::parse(edb.url())?;
+		let addr = format!("{}:{}", url.host_str().unwrap(), url.port().unwrap());
+		let other = edb.with_token(crate::mock::TOKEN.to_string());
+		drop(edb);
+		other.put("key", "value")?;
+		assert_eq!(&other.get("key")?, "value");
+		drop(other);
+		let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+		while std::net::TcpStream::connect(&addr).is_ok() {
+			assert!(std::time::Instant::now() < deadline, "server still running");
+			std::thread::sleep(std::time::Duration::from_millis(10));
+		}
+		Ok(())
+	}
+	#[test]
+	fn list_ordered_with_codec() -> EdbResult<()> {
+		/// Stores every string reversed.
+		#[derive(Debug)]
+		struct Reversed;
+		fn reverse(value: Json) -> Json {
+			match value {
+				Json::String(s) => Json::String(s.chars().rev().collect()),
+				Json::Array(items) => items.into_iter().map(reverse).collect(),
+				Json::Object(map) => map.into_iter().map(|(k, v)| (k, reverse(v))).collect(),
+				other => other,
+			}
+		}
+		impl crate::JsonCodec for Reversed {
+			fn encode(&self, value: &Json) -> EdbResult<String> {
+				Ok(reverse(value.clone()).to_string())
+			}
+			fn decode(&self, bytes: &[u8]) -> EdbResult<Json> {
+				Ok(reverse(serde_json::from_slice(bytes)?))
+			}
+		}
+
+		let server = MockServer::start()?;
+		server.handle(|request| match request.key() {
+			None => Some(Response::new(200, r#"{"b": "olleh", "a": ["dlrow"]}"#)),
+			Some(_) => None,
+		});
+		let edb = mock_builder(&server).json_codec(Reversed).build()?;
+		let entries = edb.list_ordered()?;
+		let expected = vec![
+			("b".to_string(), json!("hello")),
+			("a".to_string(), json!(["world"])),
+		];
+		assert_eq!(entries, expected);
+		Ok(())
+	}
+	#[test]
+	fn checksums_export_round_trip() -> EdbResult<()> {
+		let server = MockServer::start()?;
+		let edb = mock_builder(&server).checksums(true).build()?;
+		edb.put("a", "hello")?;
+		edb.put_json("b", json!({"y": [1, 2], "x": null}))?;
+		let expected = vec![
+			("a".to_string(), json!("hello")),
+			("b".to_string(), json!({"x": null, "y": [1, 2]})),
+		];
+		assert_eq!(edb.list_ordered()?, expected);
+
+		let mut backup = Vec::new();
+		assert_eq!(edb.export_json(&mut backup)?, 2);
+		let exported: Json = serde_json::from_slice(&backup)?;
+		assert_eq!(
+			exported,
+			json!({"a": "hello", "b": {"x": null, "y": [1, 2]}})
+		);
+		edb.clear()?;
+		assert!(edb
+			.import_json(&backup[..], OnConflict::Error)?
+			.is_success());
+		assert_eq!(edb.list_ordered()?, expected);
+		assert_eq!(server.store()["a"]["v"], json!("hello"));
+		Ok(())
+	}
 }
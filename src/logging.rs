@@ -0,0 +1,18 @@
+//! Logging macros that forward to the `log` crate when the `logging` feature is enabled, and
+//! compile to nothing (while still type-checking their arguments) when it isn't.
+//!
+//! Nothing logged here may include the token.
+
+#[cfg(feature = "logging")]
+macro_rules! edb_log {
+	($level:ident, $($arg:tt)+) => {
+		log::$level!($($arg)+)
+	};
+}
+
+#[cfg(not(feature = "logging"))]
+macro_rules! edb_log {
+	($level:ident, $($arg:tt)+) => {{
+		let _ = format_args!($($arg)+);
+	}};
+}
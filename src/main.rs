@@ -1,38 +1,273 @@
 //! An example usage of easydb using an interactive prompt
 
-mod easydb;
-use crate::easydb::EasyDB;
+use easydb::{DbStats, EasyDB, Json, OnConflict};
 use std::{
 	env::args,
-	io::{stdin, stdout, Write},
+	error::Error,
+	fs::{read_to_string, File},
+	io::{stdin, stdout, BufReader, BufWriter, Write},
+	process::exit,
+	str::FromStr,
+	time::Duration,
 };
-mod errors;
-
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-	let edb;
-	let args: Vec<_> = args().collect();
-	match args.len() {
-		1 => loop {
-			match EasyDB::new() {
-				Ok(x) => {
-					edb = x;
-					break;
-				}
+
+const USAGE: &str = "Usage: easydb [--timeout <secs>] [--retries <n>] [--raw] [--json] \
+	 [--format <json|csv>] [--script <file> [--keep-going] | --output <file> | --input <file>] \
+	 [<UUID> <Token> [URL]]";
+
+/// The file format for `export`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Format {
+	#[default]
+	Json,
+	Csv,
+}
+
+impl FromStr for Format {
+	type Err = ();
+	fn from_str(s: &str) -> Result<Self, ()> {
+		match s {
+			"json" => Ok(Format::Json),
+			"csv" => Ok(Format::Csv),
+			_ => Err(()),
+		}
+	}
+}
+
+/// Command line arguments, with flags separated from positional arguments.
+#[derive(Debug, Default)]
+struct Args {
+	positional: Vec<String>,
+	timeout: Option<Duration>,
+	retries: Option<u32>,
+	raw: bool,
+	json: bool,
+	script: Option<String>,
+	keep_going: bool,
+	format: Format,
+	output: Option<String>,
+	input: Option<String>,
+}
+
+fn parse_args<I>(args: I) -> Result<Args, String>
+where
+	I: IntoIterator<Item = String>,
+{
+	let mut parsed = Args::default();
+	let mut args = args.into_iter();
+	while let Some(arg) = args.next() {
+		match &arg[..] {
+			"--timeout" => {
+				let secs: f64 = flag_value(&arg, args.next())?;
+				let timeout = Duration::try_from_secs_f64(secs)
+					.map_err(|_| format!("Invalid value for --timeout: {}", secs))?;
+				parsed.timeout = Some(timeout);
+			}
+			"--retries" => parsed.retries = Some(flag_value(&arg, args.next())?),
+			"--raw" => parsed.raw = true,
+			"--json" => parsed.json = true,
+			"--script" => parsed.script = Some(flag_value(&arg, args.next())?),
+			"--keep-going" => parsed.keep_going = true,
+			"--format" => parsed.format = flag_value(&arg, args.next())?,
+			"--output" => parsed.output = Some(flag_value(&arg, args.next())?),
+			"--input" => parsed.input = Some(flag_value(&arg, args.next())?),
+			_ if arg.starts_with("--") => return Err(format!("Unknown flag: {}", arg)),
+			_ => parsed.positional.push(arg),
+		}
+	}
+	Ok(parsed)
+}
+
+fn flag_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+	let value = value.ok_or_else(|| format!("Missing value for {}", flag))?;
+	value
+		.parse()
+		.map_err(|_| format!("Invalid value for {}: {}", flag, value))
+}
+
+/// A command in a script file.
+#[derive(Debug, PartialEq)]
+enum Command {
+	Get(String),
+	Put(String, String),
+	Del(String),
+	Clear,
+	Stats,
+	Health,
+	Export(String),
+	Import(String),
+}
+
+/// Parses one line of a script. Blank lines and lines starting with `#` are `None`.
+///
+/// A `put` value is the rest of the line, so it may contain spaces, but keys and file names may
+/// not.
+fn parse_line(line: &str) -> Result<Option<Command>, String> {
+	let line = line.trim();
+	if line.is_empty() || line.starts_with('#') {
+		return Ok(None);
+	}
+	let (name, rest) = match line.find(char::is_whitespace) {
+		Some(i) => (&line[..i], line[i..].trim_start()),
+		None => (line, ""),
+	};
+	let (key, value) = match rest.find(char::is_whitespace) {
+		Some(i) => (&rest[..i], rest[i..].trim_start()),
+		None => (rest, ""),
+	};
+	let command = match (name, key.is_empty(), value.is_empty()) {
+		("get", false, true) => Command::Get(key.to_string()),
+		("put", false, _) => Command::Put(key.to_string(), value.to_string()),
+		("del", false, true) => Command::Del(key.to_string()),
+		("clear", true, _) => Command::Clear,
+		("stats", true, _) => Command::Stats,
+		("health", true, _) => Command::Health,
+		("export", false, true) => Command::Export(key.to_string()),
+		("import", false, true) => Command::Import(key.to_string()),
+		("get", _, _)
+		| ("put", _, _)
+		| ("del", _, _)
+		| ("clear", _, _)
+		| ("stats", _, _)
+		| ("health", _, _)
+		| ("export", _, _)
+		| ("import", _, _) => return Err(format!("Wrong arguments for {}", name)),
+		_ => return Err(format!("Unknown command: {}", name)),
+	};
+	Ok(Some(command))
+}
+
+/// Runs every command in `script` with `exec`, returning each line number (counting from 1) with
+/// its result. Stops after the first failure unless `keep_going` is set.
+fn run_script<F>(
+	script: &str,
+	keep_going: bool,
+	mut exec: F,
+) -> Vec<(usize, Result<String, String>)>
+where
+	F: FnMut(&Command) -> Result<String, Box<dyn Error>>,
+{
+	let mut results = Vec::new();
+	for (i, line) in script.lines().enumerate() {
+		let result = match parse_line(line) {
+			Ok(None) => continue,
+			Ok(Some(command)) => exec(&command).map_err(|e| e.to_string()),
+			Err(e) => Err(e),
+		};
+		let failed = result.is_err();
+		results.push((i + 1, result));
+		if failed && !keep_going {
+			break;
+		}
+	}
+	results
+}
+
+/// Runs a script command against the database. A non-2xx status, a failed health check, or a
+/// key that couldn't be imported counts as a failure.
+///
+/// `args` decides whether `stats` prints JSON and which format `export` writes.
+fn execute(edb: &EasyDB, command: &Command, args: &Args) -> Result<String, Box<dyn Error>> {
+	let outcome = match command {
+		Command::Get(key) => return Ok(display_value(&edb.get_json(key)?)?),
+		Command::Put(key, value) => edb.put(key, value)?,
+		Command::Del(key) => edb.delete(key)?,
+		Command::Clear => return Ok(format!("Deleted {} items", edb.clear_count()?)),
+		Command::Stats => return Ok(format_stats(&edb.stats()?, args.json)?),
+		Command::Health => {
+			let health = edb.health()?;
+			return if health.is_healthy() {
+				Ok(health.to_string())
+			} else {
+				Err(health.to_string().into())
+			};
+		}
+		Command::Export(path) => {
+			let count = export(edb, path, args.format)?;
+			return Ok(format!("Exported {} entries to {}", count, path));
+		}
+		Command::Import(path) => {
+			let count = import(edb, path)?;
+			return Ok(format!("Imported {} entries from {}", count, path));
+		}
+	};
+	if outcome.is_success() {
+		Ok(format!("Code: {}", outcome.status()))
+	} else {
+		Err(format!("Server returned status {}", outcome.status()).into())
+	}
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+	let args = parse_args(args().skip(1)).unwrap_or_else(|e| {
+		eprintln!("{}", e);
+		eprintln!("{}", USAGE);
+		exit(1);
+	});
+	let pos = &args.positional;
+	let mut edb = match pos.len() {
+		0 => loop {
+			match EasyDB::from_config_dir() {
+				Ok(x) => break x,
 				Err(e) => {
 					eprintln!("{}", e);
-					eprintln!("Make sure `easydb.toml` exists, then press enter.");
+					eprintln!(
+						"Make sure `easydb.toml` exists in the current directory or \
+						 `~/.config/easydb/`, then press enter."
+					);
 					input();
 				}
 			}
 		},
-		3 => edb = { EasyDB::from_uuid_token(args[1].clone(), args[2].clone(), None)? },
-		4 => {
-			edb = EasyDB::from_uuid_token(args[1].clone(), args[2].clone(), Some(args[3].clone()))?
-		}
+		2 => EasyDB::from_uuid_token(pos[0].clone(), pos[1].clone(), None)?,
+		3 => EasyDB::from_uuid_token(pos[0].clone(), pos[1].clone(), Some(pos[2].clone()))?,
 		_ => {
 			eprintln!("Invalid args, accepts 0, 2, or 3 arguments: [<UUID> <Token> [URL]]");
-			std::process::exit(1);
+			eprintln!("{}", USAGE);
+			exit(1);
+		}
+	};
+	// Flags left unset keep the library defaults.
+	if args.timeout.is_some() || args.retries.is_some() {
+		let mut builder = edb.to_builder();
+		if let Some(timeout) = args.timeout {
+			builder = builder.timeout(timeout);
+		}
+		if let Some(retries) = args.retries {
+			builder = builder.max_retries(retries);
 		}
+		edb = builder.build()?;
+	}
+	if let Some(path) = &args.script {
+		let script = read_to_string(path)?;
+		let mut failed = false;
+		let exec = |c: &Command| execute(&edb, c, &args);
+		for (line, result) in run_script(&script, args.keep_going, exec) {
+			match result {
+				Ok(message) => println!("line {}: {}", line, message),
+				Err(e) => {
+					eprintln!("line {}: {}", line, e);
+					failed = true;
+				}
+			}
+		}
+		exit(if failed { 1 } else { 0 });
+	}
+	// A backup or restore from the command line runs on its own, without the prompt.
+	let one_shot = match (&args.output, &args.input) {
+		(Some(path), _) => Some(Command::Export(path.clone())),
+		(None, Some(path)) => Some(Command::Import(path.clone())),
+		(None, None) => None,
+	};
+	if let Some(command) = one_shot {
+		match execute(&edb, &command, &args) {
+			Ok(message) => println!("{}", message),
+			Err(e) => {
+				eprintln!("{}", e);
+				exit(1);
+			}
+		}
+		return Ok(());
 	}
 	println!("EasyDB interactive prompt");
 	println!("-----------------------------------");
@@ -42,19 +277,41 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 	println!("    del      Delete an item by key");
 	println!("    list     List all items in DB");
 	println!("    clear    Delete all items");
+	println!("    stats    Summarize the sizes and types of all items");
+	println!("    health   Check the connection and token");
+	println!("    export   Write all items to a file");
+	println!("    import   Write every item in a JSON file to the DB");
 	println!("    uuid     Get UUID");
 	println!("    token    Get token");
 	println!("    url      Get URL");
 	println!("    exit     Exit the program");
 	println!();
+	println!("    Flags:");
+	println!("    --timeout <secs>    Fail requests that take longer than this");
+	println!("    --retries <n>       Retry failed requests up to n times");
+	println!("    --raw               Print values from get exactly as the server sent them");
+	println!("    --json              Print stats as JSON");
+	println!("    --script <file>     Run the commands in a file instead of prompting");
+	println!("    --keep-going        Keep running a script after a command fails");
+	println!("    --format <fmt>      Export as json (the default) or csv");
+	println!("    --output <file>     Export to a file and exit");
+	println!("    --input <file>      Import a JSON file and exit");
+	println!();
 	loop {
 		print!("> ");
 		stdout().flush()?;
-		match &input()[..] {
+		let line = input();
+		match &line[..] {
 			"get" => {
 				print!("Key:");
 				stdout().flush()?;
-				println!("{}", edb.get(&input())?);
+				let key = input();
+				if args.raw {
+					edb.get_writer(&key, &mut stdout())?;
+					println!();
+				} else {
+					println!("{}", display_value(&edb.get_json(&key)?)?);
+				}
 			}
 			"put" => {
 				print!("Key:");
@@ -62,12 +319,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 				let key = input();
 				print!("Value:");
 				stdout().flush()?;
-				println!("Code: {}", edb.put(&key, &input())?);
+				println!("Code: {}", edb.put(&key, &input())?.status());
 			}
 			"del" => {
 				print!("Key:");
 				stdout().flush()?;
-				println!("Code: {}", edb.delete(&input())?);
+				println!("Code: {}", edb.delete(input())?.status());
 			}
 			"list" => {
 				for (key, val) in edb.list()?.drain() {
@@ -75,8 +332,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 				}
 			}
 			"clear" => {
-				edb.clear()?;
-				println!("Success");
+				println!("Deleted {} items", edb.clear_count()?);
+			}
+			"stats" => {
+				println!("{}", format_stats(&edb.stats()?, args.json)?);
+			}
+			"health" => {
+				println!("{}", edb.health()?);
+			}
+			"export" | "import" => {
+				print!("File:");
+				stdout().flush()?;
+				let path = input();
+				let command = if line == "export" {
+					Command::Export(path)
+				} else {
+					Command::Import(path)
+				};
+				match execute(&edb, &command, &args) {
+					Ok(message) => println!("{}", message),
+					Err(e) => println!("{}", e),
+				}
 			}
 			"uuid" => {
 				println!("{}", edb.uuid());
@@ -96,8 +372,243 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 	Ok(())
 }
 
+/// Prints strings as they are and anything else as indented JSON.
+fn display_value(value: &Json) -> serde_json::Result<String> {
+	match value {
+		Json::String(s) => Ok(s.clone()),
+		other => serde_json::to_string_pretty(other),
+	}
+}
+
+/// Writes the whole database to a new file at `path`, returning the number of entries.
+fn export(edb: &EasyDB, path: &str, format: Format) -> Result<usize, Box<dyn Error>> {
+	let mut file = BufWriter::new(File::create(path)?);
+	let count = match format {
+		Format::Json => edb.export_json(&mut file)?,
+		Format::Csv => {
+			let entries = edb.list_ordered()?;
+			write_csv(&entries, &mut file)?;
+			entries.len()
+		}
+	};
+	file.flush()?;
+	Ok(count)
+}
+
+/// Writes every key and value from the JSON file at `path`, returning the number of entries.
+fn import(edb: &EasyDB, path: &str) -> Result<usize, Box<dyn Error>> {
+	let file = BufReader::new(File::open(path)?);
+	let report = edb
+		.import_json(file, OnConflict::Overwrite)?
+		.into_result()?;
+	Ok(report.succeeded.len())
+}
+
+/// Writes `entries` as CSV with a `key,value` header. Values are written as JSON, so a string
+/// keeps its quotes and can be told apart from a number.
+fn write_csv<W: Write>(entries: &[(String, Json)], mut writer: W) -> std::io::Result<()> {
+	writeln!(writer, "key,value")?;
+	for (key, value) in entries {
+		writeln!(
+			writer,
+			"{},{}",
+			csv_field(key),
+			csv_field(&value.to_string())
+		)?;
+	}
+	Ok(())
+}
+
+/// Quotes `field` if it contains a comma, a quote, or a line break.
+fn csv_field(field: &str) -> String {
+	if field.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_string()
+	}
+}
+
+/// Formats `stats` as a table, or as indented JSON if `json` is set.
+fn format_stats(stats: &DbStats, json: bool) -> serde_json::Result<String> {
+	if json {
+		return serde_json::to_string_pretty(stats);
+	}
+	let largest = match &stats.largest {
+		Some((key, size)) => format!("{} ({} bytes)", key, size),
+		None => "-".to_string(),
+	};
+	let t = &stats.types;
+	let rows = [
+		("Keys", stats.key_count.to_string()),
+		("Total size", format!("{} bytes", stats.total_bytes)),
+		("Largest", largest),
+		("Strings", t.string.to_string()),
+		("Numbers", t.number.to_string()),
+		("Objects", t.object.to_string()),
+		("Arrays", t.array.to_string()),
+		("Booleans", t.bool.to_string()),
+		("Nulls", t.null.to_string()),
+	];
+	let lines: Vec<_> = rows
+		.iter()
+		.map(|(name, value)| format!("{:<12}{}", name, value))
+		.collect();
+	Ok(lines.join("\n"))
+}
+
 fn input() -> String {
 	let mut s = String::new();
 	stdin().read_line(&mut s).unwrap();
 	s.trim().to_string()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+
+	/// Runs commands against a map instead of a database.
+	fn exec_local(
+		store: &mut HashMap<String, String>,
+		command: &Command,
+	) -> Result<String, Box<dyn Error>> {
+		match command {
+			Command::Get(key) => Ok(store.get(key).cloned().unwrap_or_default()),
+			Command::Put(key, _) if key == "fail" => Err("Server returned status 500".into()),
+			Command::Put(key, value) => {
+				store.insert(key.clone(), value.clone());
+				Ok("Code: 200".to_string())
+			}
+			Command::Del(key) => {
+				store.remove(key);
+				Ok("Code: 200".to_string())
+			}
+			Command::Clear => {
+				let count = store.len();
+				store.clear();
+				Ok(format!("Deleted {} items", count))
+			}
+			Command::Stats => Ok(format!("Keys        {}", store.len())),
+			Command::Health => Ok("Healthy".to_string()),
+			Command::Export(_) => Ok(format!("Exported {} entries", store.len())),
+			Command::Import(_) => Ok("Imported 0 entries".to_string()),
+		}
+	}
+
+	#[test]
+	fn parse_lines() {
+		assert_eq!(parse_line("  # comment"), Ok(None));
+		assert_eq!(parse_line(""), Ok(None));
+		assert_eq!(parse_line("get a"), Ok(Some(Command::Get("a".to_string()))));
+		assert_eq!(
+			parse_line("put a hello  world "),
+			Ok(Some(Command::Put(
+				"a".to_string(),
+				"hello  world".to_string()
+			)))
+		);
+		assert_eq!(
+			parse_line("put a"),
+			Ok(Some(Command::Put("a".to_string(), String::new())))
+		);
+		assert_eq!(parse_line("del a"), Ok(Some(Command::Del("a".to_string()))));
+		assert_eq!(parse_line("clear"), Ok(Some(Command::Clear)));
+		assert_eq!(parse_line("stats"), Ok(Some(Command::Stats)));
+		assert!(parse_line("stats a").is_err());
+		assert_eq!(parse_line("health"), Ok(Some(Command::Health)));
+		assert!(parse_line("del").is_err());
+		assert!(parse_line("get a b").is_err());
+		assert!(parse_line("clear a").is_err());
+		assert_eq!(
+			parse_line("export backup.json"),
+			Ok(Some(Command::Export("backup.json".to_string())))
+		);
+		assert_eq!(
+			parse_line("import backup.json"),
+			Ok(Some(Command::Import("backup.json".to_string())))
+		);
+		assert!(parse_line("export").is_err());
+		assert!(parse_line("fly a").is_err());
+	}
+
+	#[test]
+	fn example_script() {
+		let mut store = HashMap::new();
+		let script = include_str!("../examples/setup.edb");
+		let results = run_script(script, false, |c| exec_local(&mut store, c));
+		assert!(results.iter().all(|(_, result)| result.is_ok()));
+		assert_eq!(store["greeting"], "hello world");
+		assert!(!store.contains_key("temporary"));
+	}
+
+	#[test]
+	fn script_errors() {
+		let script = "put a 1\nput fail 2\nbogus\nput b 3\n";
+		let mut store = HashMap::new();
+		let results = run_script(script, false, |c| exec_local(&mut store, c));
+		assert_eq!(results.len(), 2);
+		assert_eq!(results[1].0, 2);
+		assert!(results[1].1.is_err());
+		assert!(!store.contains_key("b"));
+
+		let results = run_script(script, true, |c| exec_local(&mut store, c));
+		let lines: Vec<_> = results
+			.iter()
+			.map(|(line, result)| (*line, result.is_ok()))
+			.collect();
+		assert_eq!(lines, [(1, true), (2, false), (3, false), (4, true)]);
+		assert_eq!(store["b"], "3");
+	}
+
+	#[test]
+	fn stats_output() -> Result<(), Box<dyn Error>> {
+		let mut stats = DbStats {
+			key_count: 2,
+			total_bytes: 20,
+			largest: Some(("big".to_string(), 15)),
+			..DbStats::default()
+		};
+		stats.types.string = 1;
+		stats.types.object = 1;
+		let table = format_stats(&stats, false)?;
+		let lines: Vec<_> = table.lines().collect();
+		assert_eq!(lines.len(), 9);
+		assert_eq!(lines[0], "Keys        2");
+		assert_eq!(lines[2], "Largest     big (15 bytes)");
+		assert_eq!(lines[5], "Objects     1");
+
+		let json: Json = serde_json::from_str(&format_stats(&stats, true)?)?;
+		assert_eq!(json["key_count"], 2);
+		assert_eq!(json["largest"], serde_json::json!(["big", 15]));
+		assert_eq!(json["types"]["string"], 1);
+
+		let empty = format_stats(&DbStats::default(), false)?;
+		assert!(empty.contains("Largest     -"));
+		Ok(())
+	}
+
+	#[test]
+	fn csv_output() -> Result<(), Box<dyn Error>> {
+		let entries = vec![
+			("plain".to_string(), serde_json::json!(3)),
+			("greeting".to_string(), serde_json::json!("hello, world")),
+			("a,b".to_string(), serde_json::json!({"x": [1, 2]})),
+		];
+		let mut out = Vec::new();
+		write_csv(&entries, &mut out)?;
+		assert_eq!(
+			String::from_utf8(out)?,
+			"key,value\nplain,3\ngreeting,\"\"\"hello, world\"\"\"\n\"a,b\",\"{\"\"x\"\":[1,2]}\"\n"
+		);
+		Ok(())
+	}
+
+	#[test]
+	fn format_flag() {
+		let args = parse_args(["--format", "csv", "--output", "db.csv"].map(String::from)).unwrap();
+		assert_eq!(args.format, Format::Csv);
+		assert_eq!(args.output.as_deref(), Some("db.csv"));
+		assert_eq!(parse_args(Vec::new()).unwrap().format, Format::Json);
+		assert!(parse_args(["--format", "xml"].map(String::from)).is_err());
+	}
+}
@@ -0,0 +1,339 @@
+//! A minimal stand-in for easydb.io, used by the tests and by
+//! [`EasyDB::in_memory`][crate::EasyDB::in_memory].
+//!
+//! The server speaks just enough HTTP/1.1 for `reqwest`: every connection carries exactly one
+//! request and is closed after the response. Stored values live in memory. The tests can also
+//! inspect every request received, delay responses, and install a handler that overrides the
+//! response for specific requests; none of that is compiled into the library itself.
+
+use crate::Json;
+use serde_json::json;
+use std::{
+	collections::HashMap,
+	io::{self, BufRead, BufReader, Read, Write},
+	net::{SocketAddr, TcpListener, TcpStream},
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex,
+	},
+	thread,
+	time::Duration,
+};
+
+pub const UUID: &str = "test-uuid";
+pub const TOKEN: &str = "test-token";
+
+/// A request as seen by the server.
+#[derive(Clone, Debug)]
+pub struct Request {
+	pub method: String,
+	pub path: String,
+	pub headers: Vec<(String, String)>,
+	pub body: Vec<u8>,
+}
+
+impl Request {
+	/// Returns the first header named `name` (case insensitive).
+	pub fn header(&self, name: &str) -> Option<&str> {
+		let name = name.to_lowercase();
+		self.headers
+			.iter()
+			.find(|(k, _)| *k == name)
+			.map(|(_, v)| &v[..])
+	}
+	/// Returns the key this request targets, or `None` for requests against the database URL.
+	pub fn key(&self) -> Option<&str> {
+		let path = self.path.split('?').next().unwrap();
+		let rest = path.strip_prefix(&format!("/database/{}", UUID))?;
+		rest.strip_prefix('/')
+	}
+}
+
+/// A canned response.
+#[derive(Clone, Debug)]
+pub struct Response {
+	pub status: u16,
+	pub headers: Vec<(String, String)>,
+	pub body: Vec<u8>,
+}
+
+impl Response {
+	pub fn new(status: u16, body: impl Into<Vec<u8>>) -> Self {
+		Self {
+			status,
+			headers: Vec::new(),
+			body: body.into(),
+		}
+	}
+	pub fn json(status: u16, value: &Json) -> Self {
+		Self::new(status, value.to_string()).header("Content-Type", "application/json")
+	}
+	pub fn header(mut self, name: &str, value: &str) -> Self {
+		self.headers.push((name.to_string(), value.to_string()));
+		self
+	}
+}
+
+#[cfg(test)]
+type Handler = Box<dyn FnMut(&Request) -> Option<Response> + Send>;
+
+#[derive(Default)]
+struct State {
+	store: HashMap<String, Json>,
+	#[cfg(test)]
+	handler: Option<Handler>,
+	#[cfg(test)]
+	delay: Duration,
+	#[cfg(test)]
+	body_delay: Duration,
+	#[cfg(test)]
+	requests: Vec<Request>,
+	#[cfg(test)]
+	in_flight: usize,
+	#[cfg(test)]
+	max_in_flight: usize,
+}
+
+/// Stops the server's listener thread when dropped. Requests already being answered finish
+/// first.
+#[derive(Debug)]
+pub struct Shutdown {
+	addr: SocketAddr,
+	stop: Arc<AtomicBool>,
+}
+
+impl Drop for Shutdown {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::SeqCst);
+		// The listener is blocked waiting for a connection, so give it one.
+		let _ = TcpStream::connect(self.addr);
+	}
+}
+
+/// A running mock server. The server thread lives until the `MockServer` and every
+/// [`guard`][MockServer::guard] taken from it are dropped.
+pub struct MockServer {
+	url: String,
+	#[cfg(test)]
+	state: Arc<Mutex<State>>,
+	shutdown: Arc<Shutdown>,
+}
+
+impl MockServer {
+	/// Starts a server on a random localhost port.
+	pub fn start() -> io::Result<Self> {
+		let listener = TcpListener::bind("127.0.0.1:0")?;
+		let addr = listener.local_addr()?;
+		let url = format!("http://{}/database/", addr);
+		let state = Arc::new(Mutex::new(State::default()));
+		let stop = Arc::new(AtomicBool::new(false));
+		let thread_state = state.clone();
+		let thread_stop = stop.clone();
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				if thread_stop.load(Ordering::SeqCst) {
+					break;
+				}
+				let state = thread_state.clone();
+				if let Ok(stream) = stream {
+					thread::spawn(move || handle(stream, &state));
+				}
+			}
+		});
+		let shutdown = Arc::new(Shutdown { addr, stop });
+		Ok(Self {
+			url,
+			#[cfg(test)]
+			state,
+			shutdown,
+		})
+	}
+	/// The base URL, suitable for passing as the `url` of an `EasyDB`.
+	pub fn url(&self) -> &str {
+		&self.url
+	}
+	/// Returns a guard that keeps the server running after this `MockServer` is dropped.
+	pub fn guard(&self) -> Arc<Shutdown> {
+		self.shutdown.clone()
+	}
+}
+
+#[cfg(test)]
+impl MockServer {
+	/// Stores `value` under `key` without going through HTTP.
+	pub fn insert(&self, key: &str, value: Json) {
+		self.state
+			.lock()
+			.unwrap()
+			.store
+			.insert(key.to_string(), value);
+	}
+	/// A copy of the stored data.
+	pub fn store(&self) -> HashMap<String, Json> {
+		self.state.lock().unwrap().store.clone()
+	}
+	/// Every request received so far, in order.
+	pub fn requests(&self) -> Vec<Request> {
+		self.state.lock().unwrap().requests.clone()
+	}
+	/// Installs a handler that runs before the default behavior. Returning `Some` overrides the
+	/// response, returning `None` falls through to the in-memory store.
+	pub fn handle<F>(&self, handler: F)
+	where
+		F: FnMut(&Request) -> Option<Response> + Send + 'static,
+	{
+		self.state.lock().unwrap().handler = Some(Box::new(handler));
+	}
+	/// Makes every request take at least `delay` before it's answered. Requests are delayed
+	/// concurrently.
+	pub fn set_delay(&self, delay: Duration) {
+		self.state.lock().unwrap().delay = delay;
+	}
+	/// Makes every response pause for `delay` between its headers and its body.
+	pub fn set_body_delay(&self, delay: Duration) {
+		self.state.lock().unwrap().body_delay = delay;
+	}
+	/// The most requests that were being handled at the same time, from when they were read
+	/// until their response was written.
+	pub fn max_in_flight(&self) -> usize {
+		self.state.lock().unwrap().max_in_flight
+	}
+}
+
+fn handle(mut stream: TcpStream, state: &Mutex<State>) {
+	let request = match read_request(&mut stream) {
+		Some(r) => r,
+		None => return,
+	};
+	#[cfg(test)]
+	let pause = {
+		let delay = {
+			let mut state = state.lock().unwrap();
+			state.in_flight += 1;
+			state.max_in_flight = state.max_in_flight.max(state.in_flight);
+			state.delay
+		};
+		thread::sleep(delay);
+		state.lock().unwrap().body_delay
+	};
+	#[cfg(not(test))]
+	let pause = Duration::ZERO;
+	let response = respond(&mut state.lock().unwrap(), &request);
+	let finished = || {
+		#[cfg(test)]
+		{
+			state.lock().unwrap().in_flight -= 1;
+		}
+	};
+	let body = request.method != "HEAD";
+	let _ = write_response(&mut stream, &response, body, pause, finished);
+}
+
+/// Answers `request`, with the test handler if one is installed and overrides it.
+fn respond(state: &mut State, request: &Request) -> Response {
+	#[cfg(test)]
+	{
+		state.requests.push(request.clone());
+		if let Some(response) = state.handler.as_mut().and_then(|h| h(request)) {
+			return response;
+		}
+	}
+	default_response(&mut state.store, request)
+}
+
+fn default_response(store: &mut HashMap<String, Json>, request: &Request) -> Response {
+	if request.header("token") != Some(TOKEN) {
+		return Response::new(401, "Unauthorized");
+	}
+	match (&request.method[..], request.key()) {
+		("GET", None) => Response::json(200, &json!(store)),
+		("GET" | "HEAD", Some(key)) => Response::json(200, store.get(key).unwrap_or(&json!(""))),
+		("POST", Some(key)) => match serde_json::from_slice::<Json>(&request.body) {
+			Ok(Json::Object(mut body)) if body.contains_key("value") => {
+				store.insert(key.to_string(), body.remove("value").unwrap());
+				Response::new(200, "")
+			}
+			_ => Response::new(400, "Bad Request"),
+		},
+		("DELETE", Some(key)) => {
+			store.remove(key);
+			Response::new(200, "")
+		}
+		_ => Response::new(404, "Not Found"),
+	}
+}
+
+fn read_request(stream: &mut TcpStream) -> Option<Request> {
+	let mut reader = BufReader::new(stream);
+	let mut line = String::new();
+	reader.read_line(&mut line).ok()?;
+	let mut parts = line.split_whitespace();
+	let method = parts.next()?.to_string();
+	let path = parts.next()?.to_string();
+	let mut headers = Vec::new();
+	loop {
+		let mut line = String::new();
+		reader.read_line(&mut line).ok()?;
+		let line = line.trim_end();
+		if line.is_empty() {
+			break;
+		}
+		let (name, value) = line.split_at(line.find(':')?);
+		headers.push((name.to_lowercase(), value[1..].trim().to_string()));
+	}
+	let len = headers
+		.iter()
+		.find(|(k, _)| k == "content-length")
+		.and_then(|(_, v)| v.parse().ok())
+		.unwrap_or(0);
+	let mut body = vec![0; len];
+	reader.read_exact(&mut body).ok()?;
+	Some(Request {
+		method,
+		path,
+		headers,
+		body,
+	})
+}
+
+/// Writes `response`, leaving out the body (but not its length) unless `body` is set. The body
+/// is sent `pause` after the headers, and `finished` is called just before the last byte, so the
+/// client can't have read the whole response yet.
+fn write_response(
+	stream: &mut TcpStream,
+	response: &Response,
+	body: bool,
+	pause: Duration,
+	finished: impl FnOnce(),
+) -> io::Result<()> {
+	let mut head = format!("HTTP/1.1 {} Mock\r\n", response.status);
+	if !response
+		.headers
+		.iter()
+		.any(|(name, _)| name.eq_ignore_ascii_case("date"))
+	{
+		let now = httpdate::fmt_http_date(std::time::SystemTime::now());
+		head.push_str(&format!("Date: {}\r\n", now));
+	}
+	for (name, value) in &response.headers {
+		head.push_str(&format!("{}: {}\r\n", name, value));
+	}
+	head.push_str(&format!(
+		"Content-Length: {}\r\nConnection: close\r\n\r\n",
+		response.body.len()
+	));
+	let mut bytes = head.into_bytes();
+	let head_len = bytes.len();
+	if body {
+		bytes.extend_from_slice(&response.body);
+	}
+	let last = bytes.pop().unwrap();
+	let (head, rest) = bytes.split_at(head_len.min(bytes.len()));
+	stream.write_all(head)?;
+	stream.flush()?;
+	thread::sleep(pause);
+	stream.write_all(rest)?;
+	finished();
+	stream.write_all(&[last])?;
+	stream.flush()
+}
@@ -0,0 +1,40 @@
+use crate::Json;
+use serde::{
+	de::{Deserializer, MapAccess, Visitor},
+	Deserialize,
+};
+use std::{fmt, marker::PhantomData};
+
+/// The members of a JSON object, in the order they appear in the document.
+///
+/// Nested objects are regular [`Json`] values, so only the top-level order is kept. Use
+/// `OrderedEntries<IgnoredAny>` to read only the keys.
+pub(crate) struct OrderedEntries<V = Json>(pub Vec<(String, V)>);
+
+impl<'de, V: Deserialize<'de>> Deserialize<'de> for OrderedEntries<V> {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		deserializer.deserialize_map(EntriesVisitor(PhantomData))
+	}
+}
+
+struct EntriesVisitor<V>(PhantomData<V>);
+
+impl<'de, V: Deserialize<'de>> Visitor<'de> for EntriesVisitor<V> {
+	type Value = OrderedEntries<V>;
+	fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("a JSON object")
+	}
+	fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+	where
+		A: MapAccess<'de>,
+	{
+		let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+		while let Some(entry) = map.next_entry()? {
+			entries.push(entry);
+		}
+		Ok(OrderedEntries(entries))
+	}
+}
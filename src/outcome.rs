@@ -0,0 +1,75 @@
+use crate::errors::EdbError;
+use reqwest::StatusCode;
+use std::fmt;
+
+/// The result of a write, as returned by [`put`][crate::EasyDB::put] and
+/// [`delete`][crate::EasyDB::delete].
+///
+/// Common statuses get their own variant, and anything else keeps its number.
+/// [`status`][WriteOutcome::status] gives back the raw status code either way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WriteOutcome {
+	/// 201 Created.
+	Created,
+	/// 200 OK.
+	Updated,
+	/// 401 Unauthorized, usually a wrong token.
+	Unauthorized,
+	/// 429 Too Many Requests.
+	RateLimited,
+	/// Any 5xx status.
+	ServerError(u16),
+	/// Any other status, including other 2xx statuses.
+	Other(u16),
+}
+
+impl WriteOutcome {
+	/// Returns the raw status code.
+	pub fn status(self) -> u16 {
+		match self {
+			WriteOutcome::Created => 201,
+			WriteOutcome::Updated => 200,
+			WriteOutcome::Unauthorized => 401,
+			WriteOutcome::RateLimited => 429,
+			WriteOutcome::ServerError(status) | WriteOutcome::Other(status) => status,
+		}
+	}
+	/// Returns `true` for any 2xx status.
+	pub fn is_success(self) -> bool {
+		(200..300).contains(&self.status())
+	}
+	/// A [`BadStatus`][crate::errors::EdbErrorKind::BadStatus] error describing this outcome.
+	pub(crate) fn into_error(self) -> EdbError {
+		let reason = StatusCode::from_u16(self.status())
+			.ok()
+			.and_then(|status| status.canonical_reason())
+			.unwrap_or("Unknown status");
+		EdbError::bad_status(self.status(), reason.to_string())
+	}
+}
+
+impl From<u16> for WriteOutcome {
+	fn from(status: u16) -> Self {
+		match status {
+			201 => WriteOutcome::Created,
+			200 => WriteOutcome::Updated,
+			401 => WriteOutcome::Unauthorized,
+			429 => WriteOutcome::RateLimited,
+			500..=599 => WriteOutcome::ServerError(status),
+			_ => WriteOutcome::Other(status),
+		}
+	}
+}
+
+impl fmt::Display for WriteOutcome {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			WriteOutcome::Created => write!(f, "201 Created"),
+			WriteOutcome::Updated => write!(f, "200 Updated"),
+			WriteOutcome::Unauthorized => write!(f, "401 Unauthorized"),
+			WriteOutcome::RateLimited => write!(f, "429 Rate limited"),
+			WriteOutcome::ServerError(status) => write!(f, "{} Server error", status),
+			WriteOutcome::Other(status) => write!(f, "{}", status),
+		}
+	}
+}
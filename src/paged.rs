@@ -0,0 +1,86 @@
+use crate::Json;
+
+/// A sorted snapshot of a database split into pages, as returned by
+/// [`paged`][crate::EasyDB::paged].
+///
+/// The whole database is fetched once, when the view is created, and every page is served
+/// from that snapshot. Changes made to the database afterwards aren't seen; create a new view
+/// to pick them up.
+///
+/// The view keeps track of a current page for [`next`][PagedView::next] and
+/// [`prev`][PagedView::prev]. It starts before the first page, so the first call to `next`
+/// returns page 0.
+///
+/// # Example
+///
+/// ```
+/// # use crate::easydb::{EasyDB, errors::EdbError};
+/// # let edb = EasyDB::in_memory()?;
+/// let mut view = edb.paged(20)?;
+/// while let Some(page) = view.next() {
+///     for (key, value) in page {
+///         println!("{}: {}", key, value);
+///     }
+/// }
+/// # Ok::<(), EdbError>(())
+/// ```
+///
+#[derive(Clone, Debug)]
+pub struct PagedView {
+	entries: Vec<(String, Json)>,
+	limit: usize,
+	current: Option<usize>,
+}
+
+impl PagedView {
+	/// A `limit` of 0 is treated as 1.
+	pub(crate) fn new(entries: Vec<(String, Json)>, limit: usize) -> Self {
+		Self {
+			entries,
+			limit: limit.max(1),
+			current: None,
+		}
+	}
+	/// Returns the number of entries in the snapshot.
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+	/// Returns `true` if the snapshot has no entries.
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+	/// Returns the number of pages. An empty snapshot has none.
+	pub fn page_count(&self) -> usize {
+		self.entries.len().div_ceil(self.limit)
+	}
+	/// Returns the number of the current page, counting from 0, or `None` before the first call
+	/// to [`next`][PagedView::next] or [`page`][PagedView::page].
+	pub fn current(&self) -> Option<usize> {
+		self.current
+	}
+	/// Returns page `n`, counting from 0, and makes it the current page. Returns `None`, leaving
+	/// the current page alone, if there is no page `n`.
+	pub fn page(&mut self, n: usize) -> Option<&[(String, Json)]> {
+		if n >= self.page_count() {
+			return None;
+		}
+		self.current = Some(n);
+		let start = n * self.limit;
+		let end = (start + self.limit).min(self.entries.len());
+		Some(&self.entries[start..end])
+	}
+	/// Moves to the page after the current one and returns it, or returns `None` after the
+	/// last page.
+	// Not `Iterator::next`: each page borrows from the view.
+	#[allow(clippy::should_implement_trait)]
+	pub fn next(&mut self) -> Option<&[(String, Json)]> {
+		let n = self.current.map_or(0, |n| n + 1);
+		self.page(n)
+	}
+	/// Moves to the page before the current one and returns it, or returns `None` at the first
+	/// page.
+	pub fn prev(&mut self) -> Option<&[(String, Json)]> {
+		let n = self.current?.checked_sub(1)?;
+		self.page(n)
+	}
+}
@@ -0,0 +1,23 @@
+//! The types most programs need, for glob importing.
+//!
+//! ```
+//! use easydb::prelude::*;
+//!
+//! fn connect() -> EdbResult<EasyDB> {
+//!     EasyDB::builder()
+//!         .uuid("aaaa...".to_string())
+//!         .token("bbbb...".to_string())
+//!         .build()
+//! }
+//!
+//! let edb: EasyDB = connect()?;
+//! let value: Json = Json::from("hello");
+//! # Ok::<(), EdbError>(())
+//! ```
+//!
+
+pub use crate::{
+	errors::{EdbError, EdbErrorKind, EdbResult},
+	BatchReport, DbStats, EasyDB, EasyDBBuilder, Json, Key, MissingKeyBehavior, OnConflict,
+	PagedView, RequestOptions, SyncReport, TrailingSlash, WriteOutcome,
+};
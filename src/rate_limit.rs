@@ -0,0 +1,42 @@
+use std::{
+	sync::Mutex,
+	thread,
+	time::{Duration, Instant},
+};
+
+/// Spaces requests out evenly so that no more than a fixed number start each second.
+///
+/// This is a token bucket that holds a single token, so there are no bursts: after an idle
+/// period the next request goes out at once, and every later one waits its turn.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+	interval: Duration,
+	next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+	/// Returns `None` if `requests_per_sec` isn't a positive number.
+	pub fn new(requests_per_sec: f64) -> Option<Self> {
+		if requests_per_sec.is_nan() || requests_per_sec <= 0.0 {
+			return None;
+		}
+		let interval = Duration::try_from_secs_f64(1.0 / requests_per_sec).unwrap_or(Duration::MAX);
+		Some(Self {
+			interval,
+			next: Mutex::new(Instant::now()),
+		})
+	}
+	/// Blocks until the next request may be sent.
+	pub fn acquire(&self) {
+		let wait = {
+			let mut next = self.next.lock().unwrap();
+			let now = Instant::now();
+			let start = (*next).max(now);
+			*next = start.checked_add(self.interval).unwrap_or(start);
+			start - now
+		};
+		if !wait.is_zero() {
+			thread::sleep(wait);
+		}
+	}
+}
@@ -0,0 +1,52 @@
+use reqwest::{Response, StatusCode};
+use std::{fmt, sync::Arc, time::Duration};
+
+/// A failed or finished attempt, as passed to the predicate set with
+/// [`retry_on`][crate::EasyDBBuilder::retry_on].
+#[derive(Debug)]
+pub struct RetryContext<'a> {
+	pub(crate) attempt: u32,
+	pub(crate) result: &'a reqwest::Result<Response>,
+	pub(crate) elapsed: Duration,
+}
+
+impl RetryContext<'_> {
+	/// The number of attempts made so far, counting from 1.
+	pub fn attempt(&self) -> u32 {
+		self.attempt
+	}
+	/// The status code of the response, or `None` if the attempt failed with a network error.
+	pub fn status(&self) -> Option<u16> {
+		self.result.as_ref().ok().map(|resp| resp.status().as_u16())
+	}
+	/// The network error, or `None` if the server answered.
+	pub fn error(&self) -> Option<&reqwest::Error> {
+		self.result.as_ref().err()
+	}
+	/// How long ago the first attempt started, including the delays between attempts.
+	pub fn elapsed(&self) -> Duration {
+		self.elapsed
+	}
+	/// Returns what the built-in policy would decide: retry a network error, a 5xx status, or a
+	/// 429 status. Useful for a predicate that only adds or removes a few cases.
+	pub fn is_retryable_by_default(&self) -> bool {
+		match self.result {
+			Ok(resp) => is_retryable(resp.status()),
+			Err(_) => true,
+		}
+	}
+}
+
+pub(crate) fn is_retryable(status: StatusCode) -> bool {
+	status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// A predicate set with [`retry_on`][crate::EasyDBBuilder::retry_on].
+#[derive(Clone)]
+pub(crate) struct RetryPredicate(pub Arc<dyn Fn(&RetryContext) -> bool + Send + Sync>);
+
+impl fmt::Debug for RetryPredicate {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str("RetryPredicate")
+	}
+}
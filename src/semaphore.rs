@@ -0,0 +1,40 @@
+use std::sync::{Condvar, Mutex};
+
+/// Caps how many requests are in flight at once across every thread that shares it.
+#[derive(Debug)]
+pub(crate) struct Semaphore {
+	available: Mutex<usize>,
+	released: Condvar,
+}
+
+/// A slot taken from a [`Semaphore`], given back when dropped.
+pub(crate) struct Permit<'a>(&'a Semaphore);
+
+impl Semaphore {
+	/// Returns `None` if `permits` is 0.
+	pub fn new(permits: usize) -> Option<Self> {
+		if permits == 0 {
+			return None;
+		}
+		Some(Self {
+			available: Mutex::new(permits),
+			released: Condvar::new(),
+		})
+	}
+	/// Blocks until a slot is free and takes it.
+	pub fn acquire(&self) -> Permit<'_> {
+		let mut available = self.available.lock().unwrap();
+		while *available == 0 {
+			available = self.released.wait(available).unwrap();
+		}
+		*available -= 1;
+		Permit(self)
+	}
+}
+
+impl Drop for Permit<'_> {
+	fn drop(&mut self) {
+		*self.0.available.lock().unwrap() += 1;
+		self.0.released.notify_one();
+	}
+}
@@ -0,0 +1,67 @@
+use crate::Json;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A summary of a database's contents, as returned by [`stats`][crate::EasyDB::stats].
+///
+/// Sizes are the length in bytes of each value's compact JSON text, which is close to what the
+/// server sends for it. Keys aren't counted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DbStats {
+	/// How many keys the database holds.
+	pub key_count: usize,
+	/// The total size of every value.
+	pub total_bytes: usize,
+	/// The key with the largest value and that value's size, or `None` for an empty database.
+	/// Ties go to the key that sorts first.
+	pub largest: Option<(String, usize)>,
+	/// How many values there are of each JSON type.
+	pub types: TypeCounts,
+}
+
+/// How many values of each JSON type a database holds. Part of [`DbStats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TypeCounts {
+	/// Strings, including the empty strings that easydb.io treats as missing.
+	pub string: usize,
+	/// Numbers.
+	pub number: usize,
+	/// Objects.
+	pub object: usize,
+	/// Arrays.
+	pub array: usize,
+	/// `true` or `false`.
+	pub bool: usize,
+	/// `null`.
+	pub null: usize,
+}
+
+pub(crate) fn stats(map: &HashMap<String, Json>) -> DbStats {
+	let mut stats = DbStats {
+		key_count: map.len(),
+		..DbStats::default()
+	};
+	for (key, value) in map {
+		let size = value.to_string().len();
+		stats.total_bytes += size;
+		let larger = match &stats.largest {
+			Some((largest_key, largest)) => {
+				size > *largest || (size == *largest && key < largest_key)
+			}
+			None => true,
+		};
+		if larger {
+			stats.largest = Some((key.clone(), size));
+		}
+		let count = match value {
+			Json::String(_) => &mut stats.types.string,
+			Json::Number(_) => &mut stats.types.number,
+			Json::Object(_) => &mut stats.types.object,
+			Json::Array(_) => &mut stats.types.array,
+			Json::Bool(_) => &mut stats.types.bool,
+			Json::Null => &mut stats.types.null,
+		};
+		*count += 1;
+	}
+	stats
+}
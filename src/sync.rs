@@ -0,0 +1,43 @@
+use crate::Json;
+use std::collections::HashMap;
+
+/// The changes needed to turn one set of entries into another, as returned by
+/// [`diff`][crate::EasyDB::diff] and [`reset_to`][crate::EasyDB::reset_to].
+///
+/// Each list of keys is sorted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyncReport {
+	/// Keys that didn't exist and were (or would be) added.
+	pub added: Vec<String>,
+	/// Keys whose values differed and were (or would be) overwritten.
+	pub updated: Vec<String>,
+	/// Keys that weren't wanted and were (or would be) deleted.
+	pub removed: Vec<String>,
+}
+
+impl SyncReport {
+	/// Returns `true` if there are no changes.
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+	}
+}
+
+pub(crate) fn diff(current: &HashMap<String, Json>, desired: &HashMap<String, Json>) -> SyncReport {
+	let mut report = SyncReport::default();
+	for (key, value) in desired {
+		match current.get(key) {
+			None => report.added.push(key.clone()),
+			Some(old) if old != value => report.updated.push(key.clone()),
+			Some(_) => {}
+		}
+	}
+	report.removed = current
+		.keys()
+		.filter(|key| !desired.contains_key(*key))
+		.cloned()
+		.collect();
+	report.added.sort();
+	report.updated.sort();
+	report.removed.sort();
+	report
+}
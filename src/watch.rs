@@ -0,0 +1,49 @@
+use crate::{EasyDB, Json};
+use std::{
+	sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError},
+	thread,
+	time::Duration,
+};
+
+/// Keeps the poller started by [`watch_channel`][crate::EasyDB::watch_channel] running.
+///
+/// Dropping the handle stops the poller. A request that is already in flight finishes first,
+/// but its result is never sent.
+#[derive(Debug)]
+pub struct WatchHandle {
+	// Never sent on: dropping it disconnects the poller's receiver, which is the stop signal.
+	_stop: Sender<()>,
+}
+
+pub(crate) fn spawn(
+	edb: EasyDB,
+	key: String,
+	interval: Duration,
+	mut last: Json,
+) -> (Receiver<Json>, WatchHandle) {
+	let (values, receiver) = mpsc::channel();
+	let (stop, stopped) = mpsc::channel::<()>();
+	thread::spawn(move || {
+		while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+			let value = match edb.get_json(&key) {
+				Ok(value) => value,
+				Err(e) => {
+					edb_log!(warn, "Watch of {} failed to poll: {}", key, e);
+					continue;
+				}
+			};
+			if value == last {
+				continue;
+			}
+			// The handle may have been dropped while the request was in flight.
+			if let Err(TryRecvError::Disconnected) = stopped.try_recv() {
+				break;
+			}
+			if values.send(value.clone()).is_err() {
+				break;
+			}
+			last = value;
+		}
+	});
+	(receiver, WatchHandle { _stop: stop })
+}